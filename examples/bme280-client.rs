@@ -109,6 +109,7 @@ async fn main() -> Result<(), anyhow::Error> {
             service: ServiceIdentifier::id(handle.id.clone()),
             data,
             meta: vec![],
+            max_endpoints: MAX_ENDPOINTS,
         })
         .await?;
 
@@ -81,13 +81,13 @@ fn main() -> Result<(), anyhow::Error> {
     // TODO: split service and engine setup better
 
     // Setup engine
-    let mut engine = match IotEngine::<_, _, 512>::udp(descriptors, &options, "0.0.0.0:10100", store)
-    {
-        Ok(e) => e,
-        Err(e) => {
-            return Err(anyhow::anyhow!("Failed to configure engine: {:?}", e));
-        }
-    };
+    let mut engine =
+        match IotEngine::<_, _, 512>::udp(descriptors, &options, "0.0.0.0:10100", store) {
+            Ok(e) => e,
+            Err(e) => {
+                return Err(anyhow::anyhow!("Failed to configure engine: {:?}", e));
+            }
+        };
 
     info!("Using service: {:?}", engine.id());
 
@@ -0,0 +1,14 @@
+//! Fuzz endpoint decoding, any input must decode or return an error without panicking
+#![no_main]
+
+use encdec::Decode;
+use libfuzzer_sys::fuzz_target;
+
+use dsf_iot::endpoint::{EpData, EpDescriptor, IotData, IotInfo};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = EpDescriptor::decode(data);
+    let _ = EpData::decode(data);
+    let _ = IotInfo::<8>::decode(data);
+    let _ = IotData::<8>::decode(data);
+});
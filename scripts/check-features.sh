@@ -0,0 +1,18 @@
+#!/usr/bin/env bash
+# Check supported feature sets build, including no_std sets for embedded targets.
+#
+# Usage: ./scripts/check-features.sh [TARGET]
+# TARGET defaults to thumbv7em-none-eabihf and must be installed via rustup.
+
+set -euo pipefail
+
+TARGET=${1:-thumbv7em-none-eabihf}
+
+echo "engine-only (${TARGET})"
+cargo check --lib --no-default-features --target ${TARGET}
+
+echo "engine+alloc (${TARGET})"
+cargo check --lib --no-default-features --features alloc --target ${TARGET}
+
+echo "client-full (host)"
+cargo check --all-targets
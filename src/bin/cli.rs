@@ -1,13 +1,25 @@
-use dsf_core::prelude::MaybeEncrypted;
+use std::io::Write;
+use std::str::FromStr;
+use std::time::SystemTime;
 
+use dsf_core::prelude::MaybeEncrypted;
+use dsf_core::types::Id;
+
+use dsf_iot::client::{
+    cache, composite, geo, load_profiles, parse_manifest, prometheus, stats, watch, AccessSummary,
+    ClientCache, Composite, EventTracker, Identity, Location, ObjectLayout, Profile,
+    SubscriptionEvent,
+};
+use dsf_iot::endpoint::registry;
 use dsf_iot::prelude::*;
-use dsf_rpc::{DataInfo, ServiceInfo, RegisterInfo, NsRegisterInfo, NsSearchInfo};
+use dsf_iot::senml;
+use dsf_rpc::{DataInfo, NsRegisterInfo, NsSearchInfo, RegisterInfo, ServiceInfo};
 
 use clap::Parser;
 
-use futures::prelude::*;
+use futures::{prelude::*, stream};
 
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 use tracing_subscriber::filter::LevelFilter;
 use tracing_subscriber::FmtSubscriber;
@@ -15,24 +27,180 @@ use tracing_subscriber::FmtSubscriber;
 #[derive(Debug, Parser)]
 #[clap(
     name = "DSF IoT Client",
-    about = "Distributed Service Discovery (DSF) client, used for managing dsf-iot services"
+    about = "Distributed Service Discovery (DSF) client, used for managing dsf-iot services",
+    args_override_self = true
 )]
 struct Args {
     #[clap(subcommand)]
     cmd: Command,
 
+    #[clap(long, env = "DSF_IOT_PROFILE")]
+    /// Client profile to apply (daemon socket, nameservice, key directory and output settings),
+    /// arguments provided on the command line override profile settings
+    profile: Option<String>,
+
+    #[clap(long, env = "DSF_IOT_PROFILES")]
+    /// Load client profiles from a TOML file
+    /// (defaults to ~/.config/dsf-iot/profiles.toml)
+    profiles: Option<String>,
+
     #[clap(flatten)]
     client_options: Config,
 
+    #[clap(long, value_enum, default_value = "text")]
+    /// Output format for data commands
+    format: OutputFormat,
+
+    #[clap(long, value_enum, default_value = "metric")]
+    /// Unit system for data output
+    units: UnitSystem,
+
+    #[clap(long, env = "DSF_IOT_CACHE")]
+    /// Cache retrieved service information and data in the provided file
+    cache: Option<String>,
+
+    #[clap(long, requires = "cache")]
+    /// Answer list, info and data commands from the cache without contacting the daemon
+    cached: bool,
+
+    #[clap(long)]
+    /// Encode and validate create / publish objects, printing the body layout
+    /// and an estimated object length without contacting the daemon
+    dry_run: bool,
+
+    #[clap(long, default_value = "4")]
+    /// Maximum concurrent daemon connections when fetching multiple services
+    concurrency: usize,
+
+    #[clap(long)]
+    /// Report progress to stderr when fetching multiple services
+    progress: bool,
+
+    #[clap(long, env = "DSF_IOT_KINDS")]
+    /// Load additional endpoint kind definitions from a TOML or JSON (.json) file
+    /// (defaults to ~/.config/dsf-iot/kinds.toml)
+    kinds: Option<String>,
+
     #[clap(long, default_value = "info")]
     /// Enable verbose logging
     log_level: LevelFilter,
 }
 
+/// Output formats for data commands
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+enum OutputFormat {
+    /// Human readable text
+    Text,
+    /// SenML JSON records (RFC 8428)
+    Senml,
+    /// SenML CBOR records (RFC 8428)
+    SenmlCbor,
+}
+
+/// Default kind definitions file
+fn default_kinds_file() -> Option<String> {
+    let home = std::env::var("HOME").ok()?;
+    Some(format!("{}/.config/dsf-iot/kinds.toml", home))
+}
+
+/// Default client profiles file
+fn default_profiles_file() -> Option<String> {
+    let home = std::env::var("HOME").ok()?;
+    Some(format!("{}/.config/dsf-iot/profiles.toml", home))
+}
+
+/// Locate an argument value prior to argument parsing, falling back to
+/// the provided environment variable
+fn early_arg(flag: &str, env: &str) -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    let prefix = format!("{}=", flag);
+
+    while let Some(a) = args.next() {
+        if a == flag {
+            return args.next();
+        }
+        if let Some(f) = a.strip_prefix(&prefix) {
+            return Some(f.to_string());
+        }
+    }
+
+    std::env::var(env).ok()
+}
+
+/// Locate the kind definitions file prior to argument parsing,
+/// as endpoint kinds are resolved while parsing subcommands
+fn kinds_file() -> Option<String> {
+    early_arg("--kinds", "DSF_IOT_KINDS")
+}
+
+/// Load the selected client profile prior to argument parsing,
+/// as profile settings are applied as default arguments
+fn load_profile() -> Result<Option<Profile>, anyhow::Error> {
+    let name = match early_arg("--profile", "DSF_IOT_PROFILE") {
+        Some(n) => n,
+        None => return Ok(None),
+    };
+
+    let file = match early_arg("--profiles", "DSF_IOT_PROFILES").or_else(default_profiles_file) {
+        Some(f) => f,
+        None => return Err(anyhow::anyhow!("No profiles file available")),
+    };
+
+    let mut profiles = load_profiles(&file)?;
+    match profiles.remove(&name) {
+        Some(p) => Ok(Some(p)),
+        None => Err(anyhow::anyhow!("Unknown profile '{}' (in {})", name, file)),
+    }
+}
+
+/// Apply profile defaults to subcommand options not provided by the user
+fn apply_profile(cmd: &mut Command, p: &Profile) -> Result<(), anyhow::Error> {
+    let ns = match &p.ns {
+        Some(n) => Some(
+            Id::from_str(n).map_err(|_| anyhow::anyhow!("Invalid profile nameservice '{}'", n))?,
+        ),
+        None => None,
+    };
+
+    match cmd {
+        Command::NsRegister(NsRegisterOptions { ns: s, .. })
+        | Command::NsSearch(NsSearchOptions { ns: s, .. })
+            if s.id.is_none() && s.index.is_none() =>
+        {
+            s.id = ns;
+        }
+        Command::Provision(o) if o.out_dir.is_none() => o.out_dir = p.key_dir.clone(),
+        _ => (),
+    }
+
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> Result<(), anyhow::Error> {
-    // Fetch arguments
-    let opts = Args::parse();
+    // Load additional endpoint kinds
+    let kinds = kinds_file();
+    match (&kinds, default_kinds_file()) {
+        (Some(f), _) => {
+            registry::load_file(f)?;
+        }
+        (None, Some(f)) if std::path::Path::new(&f).exists() => {
+            registry::load_file(&f)?;
+        }
+        _ => (),
+    }
+
+    // Fetch arguments, with profile settings preceding user arguments
+    let profile = load_profile()?;
+    let mut args: Vec<_> = std::env::args().collect();
+    if let Some(p) = &profile {
+        args.splice(1..1, p.args());
+    }
+
+    let mut opts = Args::parse_from(args);
+    if let Some(p) = &profile {
+        apply_profile(&mut opts.cmd, p)?;
+    }
 
     // Setup logging
     let _ = FmtSubscriber::builder()
@@ -41,6 +209,62 @@ async fn main() -> Result<(), anyhow::Error> {
 
     debug!("opts: {:?}", opts);
 
+    // Handle kind management without connecting to the daemon
+    if let Command::Kinds(k) = &opts.cmd {
+        match k {
+            KindsCommand::List => println!("{}", EpKind::variants()),
+            KindsCommand::Register(o) => {
+                let f = match kinds.or_else(default_kinds_file) {
+                    Some(f) => f,
+                    None => return Err(anyhow::anyhow!("No kinds file available")),
+                };
+
+                registry::register_kind(o.id, &o.name, &o.unit)?;
+                registry::save_file(&f)?;
+
+                info!("Registered kind '{}' (id: {}) in {}", o.name, o.id, f);
+            }
+        }
+        return Ok(());
+    }
+
+    // Handle identity commands without connecting to the daemon
+    match &opts.cmd {
+        Command::GenKeys => {
+            let (id, keys) = IotClient::generate()?;
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&Identity::new(&id, &keys))?
+            );
+            return Ok(());
+        }
+        Command::Keys(k) => return run_keys(k),
+        _ => (),
+    }
+
+    // Preview objects without connecting to the daemon
+    if opts.dry_run {
+        let layout = match &opts.cmd {
+            Command::Create(o) => ObjectLayout::page(o)?,
+            // Data objects are assumed encrypted as the service is not known
+            Command::Publish(o) => ObjectLayout::data(o, true)?,
+            _ => return Err(anyhow::anyhow!("Command not supported with --dry-run")),
+        };
+        println!("{}", layout);
+        return Ok(());
+    }
+
+    // Load cache if enabled
+    let cache = match &opts.cache {
+        Some(f) => Some(ClientCache::load(f)?),
+        None => None,
+    };
+
+    // Answer from cache when requested
+    if let (true, Some(cache)) = (opts.cached, &cache) {
+        return run_cached(&opts, cache);
+    }
+
     // Create client connector
     let mut c = match IotClient::new(opts.client_options.clone()).await {
         Ok(c) => c,
@@ -54,6 +278,16 @@ async fn main() -> Result<(), anyhow::Error> {
         }
     };
 
+    c = c.with_concurrency(opts.concurrency);
+
+    if opts.progress {
+        c = c.with_progress(print_progress);
+    }
+
+    if let Some(cache) = cache {
+        c = c.with_cache(cache);
+    }
+
     // Execute commands
     match opts.cmd {
         Command::Create(o) => {
@@ -70,8 +304,12 @@ async fn main() -> Result<(), anyhow::Error> {
             print_service_list(&[res]);
         }
         Command::List(o) => {
-            let res = c.list(o).await?;
-            print_service_list(&res);
+            let res = c.list_report(o.list.clone()).await?;
+            print_service_list(&filter_near(o, res.services.clone()));
+
+            if !res.is_ok() {
+                eprintln!("{}", res);
+            }
         }
         Command::Register(o) => {
             let res = c.register(o).await?;
@@ -82,19 +320,132 @@ async fn main() -> Result<(), anyhow::Error> {
             println!("{:?}", res);
         }
         Command::Data(o) => {
-            let (service, eps, data) = c.query(o).await?;
-            print_service_data(&service, &eps, &data);
+            let (service, eps, data) = c.query(o.query).await?;
+            output_service_data(opts.format, opts.units, &service, &eps, &data)?;
+
+            if o.stats {
+                print_stats(opts.format, &eps, &data);
+            }
         }
         Command::Subscribe(o) => {
-            let mut res = c.subscribe(o).await?;
+            let services = o.services();
+            if services.is_empty() {
+                return Err(anyhow::anyhow!("No services specified"));
+            }
 
-            while let Some(i) = res.next().await {
-                info!("{:?}", i);
+            // Use a separate connection to fetch data while subscribed
+            let mut q = Some(IotClient::new(opts.client_options.clone()).await?);
+
+            let updates = match o.reconnect {
+                true => {
+                    let c = c.with_retry(o.retry());
+                    let streams = services.iter().enumerate().map(|(i, s)| {
+                        c.subscribe_reconnect(s.clone())
+                            .map(move |e| e.map(|e| (i, e)))
+                            .boxed_local()
+                    });
+                    stream::select_all(streams).boxed_local()
+                }
+                false => c
+                    .subscribe_all(&services)
+                    .await?
+                    .map(|i| Ok((i, SubscriptionEvent::Update)))
+                    .boxed_local(),
+            };
+            let mut updates = match o.count {
+                Some(n) => updates.take(n).boxed_local(),
+                None => updates.boxed_local(),
+            };
+
+            let deadline = o.duration.map(|d| tokio::time::Instant::now() + *d);
+            let mut last = vec![None; services.len()];
+
+            loop {
+                let i = match deadline {
+                    Some(d) => match tokio::time::timeout_at(d, updates.next()).await {
+                        Ok(i) => i,
+                        Err(_) => break,
+                    },
+                    None => updates.next().await,
+                };
+                let i = match i {
+                    Some(Ok((i, SubscriptionEvent::Update))) => i,
+                    Some(Ok((i, SubscriptionEvent::Reconnected { attempts }))) => {
+                        warn!(
+                            "Resubscribed to {:?} after {} attempt(s), updates may have been missed",
+                            services[i], attempts
+                        );
+                        // The data connection is also lost where the daemon restarted
+                        q = None;
+                        i
+                    }
+                    Some(Err(e)) => return Err(e.into()),
+                    None => break,
+                };
+
+                // Re-create the data connection where required
+                let qc = match q.take() {
+                    Some(qc) => q.insert(qc),
+                    None => match IotClient::new(opts.client_options.clone()).await {
+                        Ok(qc) => q.insert(qc),
+                        Err(e) if o.reconnect => {
+                            warn!("Failed to reconnect data connection: {:?}", e);
+                            continue;
+                        }
+                        Err(e) => return Err(e.into()),
+                    },
+                };
+
+                let latest = async {
+                    let mut h = qc.handle(services[i].clone()).await?;
+                    let label = h.info().short_id.to_string();
+                    let eps = h.descriptors().map(|e| e.to_vec()).unwrap_or_default();
+
+                    h.latest().await.map(|d| (label, eps, d))
+                };
+
+                let (label, eps, latest) = match latest.await {
+                    Ok(v) => v,
+                    Err(e) if o.reconnect => {
+                        warn!("Failed to fetch data for {:?}: {:?}", services[i], e);
+                        q = None;
+                        continue;
+                    }
+                    Err(e) => return Err(e.into()),
+                };
+
+                match latest {
+                    Some(d) if Some(d.index) != last[i] => {
+                        last[i] = Some(d.index);
+                        print_update(&label, &eps, &d, opts.units);
+                    }
+                    _ => debug!("No new data for service {}", label),
+                }
             }
         }
         Command::Discover(o) => {
-            let res = c.discover(o).await?;
-            print_service_list(&res);
+            let mut res = Box::pin(c.discover_stream(o));
+            let (mut found, mut errors) = (0, 0);
+
+            while let Some(s) = res.next().await {
+                match s {
+                    Ok(s) => {
+                        found += 1;
+                        print_service_list(&[s]);
+                    }
+                    Err(e) => {
+                        errors += 1;
+                        error!("Discovery failed: {:?}", e);
+                    }
+                }
+            }
+
+            if errors > 0 {
+                return Err(anyhow::anyhow!("Discovery failed ({} errors)", errors));
+            }
+            if found == 0 {
+                return Err(anyhow::anyhow!("No services discovered"));
+            }
         }
         Command::NsRegister(o) => {
             let (r, s, d) = c.ns_register(o).await?;
@@ -104,12 +455,331 @@ async fn main() -> Result<(), anyhow::Error> {
             let (i, s) = c.ns_search(o).await?;
             print_search_info(i, &s);
         }
+        Command::Watch(o) => {
+            // Use a separate connection to fetch data while subscribed
+            let mut q = IotClient::new(opts.client_options.clone()).await?;
+            let mut h = q.handle(o.service.clone()).await?;
+
+            // Locate the watched endpoint
+            let index = h
+                .descriptors()
+                .and_then(|eps| eps.iter().position(|e| e.kind == o.endpoint))
+                .ok_or(IotError::NoEndpoint)?;
+
+            let filter = o.filter();
+            let mut last = None;
+            let mut events = EventTracker::new();
+
+            let mut updates = c
+                .subscribe(SubscribeOptions {
+                    service: o.service.clone(),
+                })
+                .await?;
+
+            while let Some(_) = updates.next().await {
+                let d = match h.latest().await? {
+                    Some(d) if Some(d.index) != last => d,
+                    _ => continue,
+                };
+                last = Some(d.index);
+
+                let value = match &d.body {
+                    MaybeEncrypted::Cleartext(values) => match values.get(index) {
+                        Some(v) => v.value.clone(),
+                        None => continue,
+                    },
+                    _ => continue,
+                };
+
+                // Event endpoints trigger on new events rather than on each value
+                let n = match &value {
+                    EpValue::Event { .. } => match events.update(&value)? {
+                        0 => {
+                            debug!("No new events: {}", value);
+                            continue;
+                        }
+                        n => n,
+                    },
+                    _ => 1,
+                };
+
+                if !filter.matches(&value) {
+                    debug!("Ignoring value: {}", value);
+                    continue;
+                }
+
+                info!(
+                    "Matched {}: {}, executing {:?}",
+                    o.endpoint,
+                    value,
+                    o.command()
+                );
+
+                let env = [
+                    ("DSF_IOT_SERVICE", h.info().id.to_string()),
+                    ("DSF_IOT_ENDPOINT", o.endpoint.to_string()),
+                    ("DSF_IOT_VALUE", value.to_string()),
+                    ("DSF_IOT_UNIT", o.endpoint.unit()),
+                    ("DSF_IOT_INDEX", d.index.to_string()),
+                    ("DSF_IOT_SIGNATURE", d.signature.to_string()),
+                    ("DSF_IOT_EVENTS", n.to_string()),
+                ];
+
+                match watch::run_action(o.command(), &env).await {
+                    Ok(s) if !s.success() => error!("Action exited with: {}", s),
+                    Err(e) => error!("Failed to execute action: {:?}", e),
+                    _ => (),
+                }
+            }
+        }
+        Command::Exporter(o) => {
+            prometheus::serve(&mut c, o.listen).await?;
+        }
+        Command::Audit(o) => {
+            let report = c.audit(o.service).await?;
+            println!("{}", report);
+
+            if !report.is_ok() {
+                return Err(anyhow::anyhow!("Service history audit failed"));
+            }
+        }
+        Command::Provision(o) => {
+            let entries = parse_manifest(&std::fs::read_to_string(&o.manifest)?)?;
+            let out_dir = o.out_dir.as_deref().unwrap_or(".");
+
+            // Write key files as devices are created
+            let report = c
+                .provision(&entries, o.public, |p| {
+                    let f = std::path::Path::new(out_dir).join(format!("{}.key", p.name));
+                    write_key_file(&f, p.identity.encrypt(&o.passphrase)?)?;
+                    info!("Wrote key file for {} to {}", p.name, f.display());
+                    Ok(())
+                })
+                .await;
+
+            println!("{}", report);
+
+            if !report.is_ok() {
+                return Err(anyhow::anyhow!("Failed to provision all devices"));
+            }
+        }
+        Command::Composite(o) => {
+            let combined = Composite::new(o.source);
+            combined.validate()?;
+
+            // Create the composite service if required
+            let target = match o.service {
+                Some(id) => id,
+                None => {
+                    let h = c
+                        .create(CreateOptions {
+                            endpoints: combined.descriptors(),
+                            public: o.public,
+                            ..Default::default()
+                        })
+                        .await?;
+                    info!("Created composite service: {:#}", h.id);
+                    h.id
+                }
+            };
+
+            // Use a separate connection for source subscriptions
+            let mut s = IotClient::new(opts.client_options.clone()).await?;
+
+            composite::run(&mut c, &mut s, ServiceIdentifier::id(target), combined).await?;
+        }
         _ => unreachable!(),
     }
 
     Ok(())
 }
 
+/// Execute service identity commands
+fn run_keys(cmd: &KeysCommand) -> Result<(), anyhow::Error> {
+    match cmd {
+        KeysCommand::Generate(o) => {
+            let (id, keys) = IotClient::generate()?;
+            let identity = Identity::new(&id, &keys);
+
+            match (&o.file, &o.passphrase) {
+                (Some(f), Some(p)) => {
+                    write_key_file(f, identity.encrypt(p)?)?;
+                    info!("Wrote encrypted identity for {} to {}", identity.id, f);
+                }
+                (Some(_), None) => return Err(anyhow::anyhow!("No passphrase provided")),
+                _ => println!("{}", serde_json::to_string_pretty(&identity)?),
+            }
+        }
+        KeysCommand::Export(o) => {
+            let identity: Identity = serde_json::from_slice(&std::fs::read(&o.input)?)?;
+
+            // Check keys are valid prior to export
+            identity.keys()?;
+
+            write_key_file(&o.file, identity.encrypt(&o.passphrase)?)?;
+            info!("Wrote encrypted identity for {} to {}", identity.id, o.file);
+        }
+        KeysCommand::Import(o) => {
+            let identity = Identity::decrypt(&std::fs::read(&o.file)?, &o.passphrase)?;
+            let s = serde_json::to_string_pretty(&identity)?;
+
+            match &o.output {
+                Some(f) => write_key_file(f, s)?,
+                None => println!("{}", s),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Write a key file readable only by the current user
+fn write_key_file(
+    path: impl AsRef<std::path::Path>,
+    data: impl AsRef<[u8]>,
+) -> Result<(), std::io::Error> {
+    use std::io::Write;
+
+    let mut opts = std::fs::OpenOptions::new();
+    opts.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        opts.mode(0o600);
+    }
+
+    let mut f = opts.open(path)?;
+
+    // Mode only applies when creating files, so also restrict existing files
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        f.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+
+    f.write_all(data.as_ref())
+}
+
+/// Execute list, info and data commands using cached information
+fn run_cached(opts: &Args, cache: &ClientCache) -> Result<(), anyhow::Error> {
+    // Cache order does not match daemon indices, so services must be identified by ID
+    let service = match &opts.cmd {
+        Command::Info(o) => Some(&o.service),
+        Command::Data(o) => Some(&o.query.service),
+        _ => None,
+    };
+    if matches!(service, Some(s) if s.id.is_none()) {
+        return Err(anyhow::anyhow!("Cached services must be identified by ID"));
+    }
+
+    match &opts.cmd {
+        Command::List(o) => {
+            print_service_list(&filter_near(o, cache.list()));
+        }
+        Command::Info(o) => match cache.find(&o.service) {
+            Some(s) => print_service(&s.info, &s.page),
+            None => error!("No cached service matching {:?}", o.service),
+        },
+        Command::Data(o) => match cache.find(&o.query.service) {
+            Some(s) => {
+                output_service_data(opts.format, opts.units, &s.info, &s.page, &s.data)?;
+                if o.stats {
+                    print_stats(opts.format, &s.page, &s.data);
+                }
+            }
+            None => error!("No cached service matching {:?}", o.query.service),
+        },
+        _ => return Err(anyhow::anyhow!("Command not supported with --cached")),
+    }
+
+    Ok(())
+}
+
+/// Output service data using the configured format
+fn output_service_data(
+    format: OutputFormat,
+    units: UnitSystem,
+    service: &ServiceInfo,
+    eps: &DataInfo<Vec<EpDescriptor>>,
+    data: &[DataInfo<Vec<EpData>>],
+) -> Result<(), anyhow::Error> {
+    match format {
+        OutputFormat::Text => print_service_data(service, eps, data, units),
+        OutputFormat::Senml => {
+            let records = senml::service_records(service, eps, data);
+            println!("{}", senml::to_json(&records)?);
+        }
+        OutputFormat::SenmlCbor => {
+            let records = senml::service_records(service, eps, data);
+            std::io::stdout().write_all(&senml::to_cbor(&records))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Print per-endpoint statistics for queried data, using stderr for
+/// machine readable formats
+fn print_stats(
+    format: OutputFormat,
+    desc: &DataInfo<Vec<EpDescriptor>>,
+    data: &[DataInfo<Vec<EpData>>],
+) {
+    let endpoints = match &desc.body {
+        MaybeEncrypted::Cleartext(eps) => eps,
+        _ => {
+            error!("Cannot compute statistics for private service without decryption");
+            return;
+        }
+    };
+
+    let objects = data.iter().filter_map(|d| match &d.body {
+        MaybeEncrypted::Cleartext(v) => Some(&v[..]),
+        _ => None,
+    });
+    let summary = stats::endpoint_stats(endpoints.len(), objects);
+
+    let mut buff = format!("Statistics ({} objects):\n", data.len());
+    for (e, s) in endpoints.iter().zip(&summary) {
+        buff.push_str(&format!("  - {:16} ({}): {}\n", e.kind, e.kind.unit(), s));
+    }
+
+    match format {
+        OutputFormat::Text => print!("{}", buff),
+        _ => eprint!("{}", buff),
+    }
+}
+
+/// Report multi-service fetch progress on stderr
+fn print_progress(done: usize, total: usize) {
+    eprint!("\rFetched {}/{} services", done, total);
+    if done == total {
+        eprintln!();
+    }
+}
+
+/// Print a single labelled data update for subscriptions
+fn print_update(label: &str, eps: &[EpDescriptor], d: &DataInfo<Vec<EpData>>, units: UnitSystem) {
+    print!("[{}] index: {}", label, d.index);
+
+    match &d.body {
+        MaybeEncrypted::Cleartext(data) => {
+            for ((e, v), d) in EpReadings::new(eps, data).calibrated().zip(data) {
+                let (value, unit) = units.apply(&e.kind, &v);
+                print!(", {}: {} {}", e.kind, value, unit);
+
+                if d.quality != EpQuality::Good {
+                    print!(" ({})", d.quality);
+                }
+            }
+            println!();
+        }
+        MaybeEncrypted::Encrypted(_) => println!(" ENCRYPTED"),
+        MaybeEncrypted::None => println!(" None"),
+    }
+}
+
 fn print_register_info(reg: NsRegisterInfo, s: &ServiceInfo, d: &DataInfo<Vec<EpDescriptor>>) {
     println!("Registered service with ns {:#}", reg.ns);
 
@@ -138,6 +808,10 @@ fn print_service(s: &ServiceInfo, d: &DataInfo<Vec<EpDescriptor>>) {
 
     println!("Primary page: {:#} (index: {})", d.signature, d.index);
 
+    if let Some(l) = Location::from_page(d) {
+        println!("Location: {}", l);
+    }
+
     print!("Endpoints: ");
     match &d.body {
         MaybeEncrypted::Cleartext(eps) => {
@@ -148,6 +822,8 @@ fn print_service(s: &ServiceInfo, d: &DataInfo<Vec<EpDescriptor>>) {
         MaybeEncrypted::None => println!("None"),
     }
 
+    println!("Control: {}", AccessSummary::from_service(s, d));
+
     match &d.private_options {
         MaybeEncrypted::Cleartext(options) if options.len() > 0 => {
             println!("  private_options: ");
@@ -170,6 +846,26 @@ fn print_service(s: &ServiceInfo, d: &DataInfo<Vec<EpDescriptor>>) {
     }
 }
 
+/// Filter listed services by location where `--near` is provided,
+/// ordering matching services by distance
+fn filter_near(
+    o: &ListServicesOptions,
+    services: Vec<(ServiceInfo, DataInfo<Vec<EpDescriptor>>)>,
+) -> Vec<(ServiceInfo, DataInfo<Vec<EpDescriptor>>)> {
+    let near = match &o.near {
+        Some(l) => l,
+        None => return services,
+    };
+
+    geo::filter_near(services, |(_s, p)| Location::from_page(p), near, o.radius)
+        .into_iter()
+        .map(|(s, d)| {
+            debug!("Service {} at {:.2} km", s.0.id, d);
+            s
+        })
+        .collect()
+}
+
 fn print_service_list(services: &[(ServiceInfo, DataInfo<Vec<EpDescriptor>>)]) {
     for (s, d) in services {
         print_service(s, d);
@@ -178,7 +874,21 @@ fn print_service_list(services: &[(ServiceInfo, DataInfo<Vec<EpDescriptor>>)]) {
 
 fn print_endpoints(eps: &[EpDescriptor]) {
     for (i, e) in eps.iter().enumerate() {
-        println!("  - {:2}: {:13} in {:4}", i, e.kind, e.kind.unit());
+        print!(
+            "  - {:2}: {:13} in {:4} {} ({})",
+            i,
+            e.kind,
+            e.kind.unit(),
+            e.flags,
+            e.role()
+        );
+        if !e.constraints.is_empty() {
+            print!(" [{}]", e.constraints);
+        }
+        if !e.calibration.is_identity() {
+            print!(" (calibration: {})", e.calibration);
+        }
+        println!();
     }
 }
 
@@ -186,6 +896,7 @@ fn print_service_data(
     service: &ServiceInfo,
     desc: &DataInfo<Vec<EpDescriptor>>,
     data: &[DataInfo<Vec<EpData>>],
+    units: UnitSystem,
 ) {
     println!("Service ID: {:#} (short: {})", service.id, service.short_id);
     println!("Primary page: {:#} (index: {})", desc.signature, desc.index);
@@ -225,20 +936,23 @@ fn print_service_data(
     }
 
     println!("Data: ");
+    let now = SystemTime::now();
     for d in data {
-        println!("Object: {:#} index: {}", d.signature, d.index);
+        match cache::expired(d, now) {
+            true => println!("Object: {:#} index: {} (expired)", d.signature, d.index),
+            false => println!("Object: {:#} index: {}", d.signature, d.index),
+        }
 
         print!("  values: ");
         match &d.body {
             MaybeEncrypted::Cleartext(data) => {
                 println!("");
-                for (i, d) in data.iter().enumerate() {
-                    println!(
-                        "    - {:16}: {:6} {}",
-                        endpoints[i].kind,
-                        d.value,
-                        endpoints[i].kind.unit()
-                    );
+                for ((e, v), d) in EpReadings::new(endpoints, data).calibrated().zip(data) {
+                    let (value, unit) = units.apply(&e.kind, &v);
+                    match d.quality {
+                        EpQuality::Good => println!("    - {:16}: {:6} {}", e.kind, value, unit),
+                        q => println!("    - {:16}: {:6} {} ({})", e.kind, value, unit, q),
+                    }
                 }
             }
             MaybeEncrypted::Encrypted(_) => println!("ENCRYPTED"),
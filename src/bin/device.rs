@@ -0,0 +1,640 @@
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{Duration, Instant, SystemTime};
+
+use clap::Parser;
+use serde::Deserialize;
+
+use dsf_core::prelude::Options;
+use dsf_engine::store::SqliteStore;
+
+use tracing::{debug, error, info, warn};
+use tracing_subscriber::filter::{EnvFilter, LevelFilter};
+use tracing_subscriber::FmtSubscriber;
+
+use dsf_iot::client::parse_location;
+use dsf_iot::endpoint::{
+    delta, diagnostic_descriptors, is_delta, parse_endpoint_kind, parse_endpoint_value, registry,
+    Diagnostics, EpRate, RatePer,
+};
+use dsf_iot::prelude::*;
+use dsf_iot::sim::{SimSensor, Waveform};
+
+#[derive(Debug, Parser)]
+#[clap(
+    name = "DSF IoT Device",
+    about = "Runs a DSF IoT engine for a sensor device, configured via a TOML file"
+)]
+struct Args {
+    #[clap(long, default_value = "/etc/dsf-iot/device.toml")]
+    /// Device configuration file
+    config: PathBuf,
+
+    #[clap(long, default_value = "info")]
+    /// Enable verbose logging
+    log_level: LevelFilter,
+}
+
+/// Device configuration, loaded from a TOML file
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct DeviceConfig {
+    /// Database file for the engine store
+    store: String,
+
+    /// Address for engine UDP communication
+    #[serde(default = "default_bind")]
+    bind: String,
+
+    /// Period for sensor readings (humantime format, e.g. `1m`)
+    #[serde(default = "default_period")]
+    period: String,
+
+    /// Service name
+    name: Option<String>,
+
+    /// Service room
+    room: Option<String>,
+
+    /// Service location (`LAT,LNG[,ALT]`)
+    location: Option<String>,
+
+    /// Service endpoints, in the order provided by the driver
+    endpoints: Vec<EndpointConfig>,
+
+    /// Derived rate endpoints, published following driver endpoints
+    #[serde(default)]
+    derived: Vec<DerivedConfig>,
+
+    /// Additional endpoint kind definitions file
+    kinds: Option<String>,
+
+    /// Period for publishing standard diagnostics endpoints (humantime format, e.g. `15m`)
+    diagnostics: Option<String>,
+
+    /// Publish differential objects containing only changed endpoints,
+    /// with a full object every `differential` objects
+    differential: Option<usize>,
+
+    /// Sensor driver
+    driver: DriverConfig,
+}
+
+fn default_bind() -> String {
+    "0.0.0.0:10100".to_string()
+}
+
+fn default_period() -> String {
+    "1m".to_string()
+}
+
+/// Consecutive tick errors before comms are considered lost and the engine reinitialised
+const COMMS_ERROR_LIMIT: usize = 10;
+
+/// Initial backoff between engine reinitialisation attempts
+const COMMS_BACKOFF_MIN: Duration = Duration::from_secs(1);
+
+/// Maximum backoff between engine reinitialisation attempts
+const COMMS_BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+/// Endpoint configuration
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct EndpointConfig {
+    /// Endpoint kind name or ID
+    kind: String,
+
+    /// Indicate the endpoint is writable
+    #[serde(default)]
+    writable: bool,
+
+    /// Endpoint role (sensor, actuator, config, or event)
+    role: Option<EpRole>,
+
+    /// Endpoint value constraints
+    #[serde(default, flatten)]
+    constraints: EpConstraints,
+
+    /// Endpoint calibration, published in the descriptor for clients to apply
+    #[serde(default)]
+    calibration: EpCalibration,
+
+    /// Apply calibration on the device prior to publishing rather than in clients
+    #[serde(default)]
+    calibrate_on_device: bool,
+}
+
+/// Derived rate endpoint configuration
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct DerivedConfig {
+    /// Endpoint kind name or ID for the derived endpoint, providing the rate unit
+    kind: String,
+
+    /// Index of the source endpoint
+    source: usize,
+
+    /// Rate time base
+    #[serde(default)]
+    per: RatePer,
+}
+
+/// Sensor driver configuration
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum DriverConfig {
+    /// Execute a command, parsing whitespace separated values (one per endpoint) from stdout
+    Command { command: Vec<String> },
+
+    /// Simulated sensor, generating one waveform per endpoint
+    Sim {
+        #[serde(default)]
+        seed: u64,
+        signals: Vec<Waveform>,
+    },
+
+    /// BME280 temperature / pressure / humidity sensor
+    #[cfg(feature = "device-bme280")]
+    Bme280 {
+        #[serde(default = "default_i2c_dev")]
+        i2c_dev: String,
+        #[serde(default = "default_bme280_addr")]
+        i2c_addr: u8,
+    },
+}
+
+#[cfg(feature = "device-bme280")]
+fn default_i2c_dev() -> String {
+    "/dev/i2c-1".to_string()
+}
+
+#[cfg(feature = "device-bme280")]
+fn default_bme280_addr() -> u8 {
+    119
+}
+
+impl DeviceConfig {
+    /// Load device configuration from a file
+    fn load(path: &PathBuf) -> Result<Self, anyhow::Error> {
+        let s = std::fs::read_to_string(path)?;
+        let c = toml::from_str(&s)?;
+        Ok(c)
+    }
+
+    /// Build endpoint descriptors from configuration
+    fn descriptors(&self) -> Result<Vec<EpDescriptor>, anyhow::Error> {
+        let mut descriptors = Vec::with_capacity(self.endpoints.len() + self.derived.len());
+
+        for e in &self.endpoints {
+            let flags = match e.writable {
+                true => EpFlags::RW,
+                false => EpFlags::R,
+            };
+
+            let mut d = EpDescriptor::new(kind(&e.kind)?, flags).with_constraints(e.constraints);
+            d.role = e.role;
+            if !e.calibrate_on_device {
+                d.calibration = e.calibration;
+            }
+
+            descriptors.push(d);
+        }
+
+        for d in &self.derived {
+            descriptors.push(EpDescriptor::new(kind(&d.kind)?, EpFlags::R));
+        }
+
+        if self.diagnostics.is_some() {
+            descriptors.extend(diagnostic_descriptors());
+        }
+
+        Ok(descriptors)
+    }
+
+    /// Build rate trackers for derived endpoints
+    fn rates(&self) -> Result<Vec<(usize, EpRate)>, anyhow::Error> {
+        let mut rates = Vec::with_capacity(self.derived.len());
+
+        for d in &self.derived {
+            let source = match self.endpoints.get(d.source) {
+                Some(e) => kind(&e.kind)?,
+                None => return Err(anyhow::anyhow!("Invalid derived source: {}", d.source)),
+            };
+
+            rates.push((d.source, EpRate::new(source, d.per)));
+        }
+
+        Ok(rates)
+    }
+
+    /// Parse the reading period
+    fn period(&self) -> Result<Duration, anyhow::Error> {
+        let p = humantime::parse_duration(&self.period)?;
+        Ok(p)
+    }
+
+    /// Build the diagnostics tracker where enabled
+    fn diagnostics(&self) -> Result<Option<Diagnostics>, anyhow::Error> {
+        match &self.diagnostics {
+            Some(p) => {
+                let p = humantime::parse_duration(p)?;
+                Ok(Some(Diagnostics::new(p.as_millis() as u64)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Check whether a new configuration requires the engine to be restarted
+    fn requires_restart(&self, other: &DeviceConfig) -> bool {
+        self.store != other.store
+            || self.bind != other.bind
+            || self.name != other.name
+            || self.room != other.room
+            || self.location != other.location
+            || self.endpoints != other.endpoints
+            || self.derived != other.derived
+            || self.kinds != other.kinds
+            || self.diagnostics != other.diagnostics
+    }
+}
+
+/// Parse an endpoint kind from configuration
+fn kind(s: &str) -> Result<EpKind, anyhow::Error> {
+    parse_endpoint_kind(s).map_err(|_| anyhow::anyhow!("Unrecognised endpoint kind: {}", s))
+}
+
+/// Sensor driver, providing endpoint data in configured endpoint order
+trait Driver {
+    fn read(&mut self) -> Result<Vec<EpData>, anyhow::Error>;
+}
+
+/// Command driver, executes a command and parses values from stdout
+struct CommandDriver {
+    command: Vec<String>,
+}
+
+impl Driver for CommandDriver {
+    fn read(&mut self) -> Result<Vec<EpData>, anyhow::Error> {
+        let (program, args) = self
+            .command
+            .split_first()
+            .ok_or(anyhow::anyhow!("No driver command specified"))?;
+
+        let out = Command::new(program).args(args).output()?;
+        if !out.status.success() {
+            return Err(anyhow::anyhow!("Driver command exited with {}", out.status));
+        }
+
+        let s = String::from_utf8(out.stdout)?;
+
+        let mut data = vec![];
+        for v in s.split_whitespace() {
+            data.push(EpData::new(parse_endpoint_value(v)?));
+        }
+
+        Ok(data)
+    }
+}
+
+/// Simulated sensor driver, for demos and testing without hardware
+struct SimDriver {
+    sensor: SimSensor,
+    start: Instant,
+}
+
+impl Driver for SimDriver {
+    fn read(&mut self) -> Result<Vec<EpData>, anyhow::Error> {
+        let t = self.start.elapsed().as_millis() as u64;
+        Ok(self.sensor.read(t))
+    }
+}
+
+#[cfg(feature = "device-bme280")]
+struct Bme280Driver {
+    sensor: bme280::BME280<linux_embedded_hal::I2cdev, linux_embedded_hal::Delay>,
+}
+
+#[cfg(feature = "device-bme280")]
+impl Driver for Bme280Driver {
+    fn read(&mut self) -> Result<Vec<EpData>, anyhow::Error> {
+        let m = self
+            .sensor
+            .measure()
+            .map_err(|e| anyhow::anyhow!("BME280 read error: {:?}", e))?;
+
+        Ok(vec![
+            EpData::new(m.temperature.into()),
+            EpData::new((m.pressure / 1000.0).into()),
+            EpData::new(m.humidity.into()),
+        ])
+    }
+}
+
+/// Create a sensor driver from configuration
+fn driver(cfg: &DriverConfig) -> Result<Box<dyn Driver>, anyhow::Error> {
+    match cfg {
+        DriverConfig::Command { command } => Ok(Box::new(CommandDriver {
+            command: command.clone(),
+        })),
+        DriverConfig::Sim { seed, signals } => Ok(Box::new(SimDriver {
+            sensor: SimSensor::new(signals.clone(), *seed),
+            start: Instant::now(),
+        })),
+        #[cfg(feature = "device-bme280")]
+        DriverConfig::Bme280 { i2c_dev, i2c_addr } => {
+            let i2c_bus = linux_embedded_hal::I2cdev::new(i2c_dev)?;
+            let mut sensor = bme280::BME280::new(i2c_bus, *i2c_addr, linux_embedded_hal::Delay);
+            sensor
+                .init()
+                .map_err(|e| anyhow::anyhow!("BME280 init error: {:?}", e))?;
+
+            Ok(Box::new(Bme280Driver { sensor }))
+        }
+    }
+}
+
+/// Fetch config file modification time for reload checks
+fn modified(path: &PathBuf) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn main() -> Result<(), anyhow::Error> {
+    // Fetch arguments
+    let args = Args::parse();
+
+    let filter = EnvFilter::from_default_env().add_directive(args.log_level.into());
+
+    // Setup logging
+    let _ = FmtSubscriber::builder().with_env_filter(filter).try_init();
+
+    debug!("args: {:?}", args);
+
+    // Load configuration
+    let mut cfg = DeviceConfig::load(&args.config)?;
+    let mut cfg_modified = modified(&args.config);
+    let mut period = cfg.period()?;
+
+    debug!("config: {:?}", cfg);
+
+    // Load additional endpoint kinds
+    if let Some(f) = &cfg.kinds {
+        registry::load_file(f)?;
+    }
+
+    // Setup engine, service configuration changes require a restart so this
+    // uses the initial configuration when reinitialising after comms failures
+    let setup = {
+        let cfg = cfg.clone();
+
+        move || -> Result<_, anyhow::Error> {
+            // Setup store / database
+            let store = match SqliteStore::new(&cfg.store) {
+                Ok(s) => s,
+                Err(e) => return Err(anyhow::anyhow!("Failed to open store: {:?}", e)),
+            };
+
+            // Setup service
+            let descriptors = IotInfo::new(&cfg.descriptors()?)
+                .map_err(|_| anyhow::anyhow!("Too many endpoints configured"))?;
+
+            let mut options = vec![];
+            if let Some(v) = &cfg.name {
+                options.push(Options::name(v));
+            }
+            if let Some(v) = &cfg.room {
+                options.push(Options::room(v));
+            }
+            if let Some(v) = &cfg.location {
+                options.push(parse_location(v)?.option());
+            }
+
+            match IotEngine::<_, _, 512>::udp(descriptors, &options, &cfg.bind, store) {
+                Ok(e) => Ok(e),
+                Err(e) => Err(anyhow::anyhow!("Failed to configure engine: {:?}", e)),
+            }
+        }
+    };
+
+    let mut engine = Some(setup()?);
+    if let Some(e) = &engine {
+        info!("Using service: {:?}", e.id());
+    }
+
+    // Differential publishing state
+    let mut published: Option<Vec<EpData>> = None;
+    let mut since_full = 0;
+
+    // Comms watchdog state
+    let mut tick_errors = 0;
+    let mut comms_lost = false;
+    let mut backoff = COMMS_BACKOFF_MIN;
+    let mut retry_at = Instant::now();
+
+    let mut sensor = driver(&cfg.driver)?;
+    let mut rates = cfg.rates()?;
+    let mut diagnostics = cfg.diagnostics()?;
+
+    // Measurements are due immediately on startup
+    let start = Instant::now();
+    let mut last: Option<Instant> = None;
+
+    // Run sensor loop
+    loop {
+        // Reinitialise the engine (rebinding comms) with backoff after comms failures
+        if engine.is_none() && Instant::now() >= retry_at {
+            match setup() {
+                Ok(e) => {
+                    info!("Reinitialised engine");
+                    engine = Some(e);
+                }
+                Err(e) => {
+                    backoff = (backoff * 2).min(COMMS_BACKOFF_MAX);
+                    retry_at = Instant::now() + backoff;
+                    error!(
+                        "Failed to reinitialise engine (retry in {:?}): {:?}",
+                        backoff, e
+                    );
+                }
+            }
+        }
+
+        // Tick engine to handle received messages etc.
+        match engine.as_mut().map(|e| e.tick()) {
+            Some(Ok(_)) => {
+                if comms_lost {
+                    info!("Comms restored");
+                    comms_lost = false;
+                    backoff = COMMS_BACKOFF_MIN;
+                }
+                tick_errors = 0;
+            }
+            Some(Err(e)) => {
+                error!("Tick error: {:?}", e);
+
+                tick_errors += 1;
+                if tick_errors >= COMMS_ERROR_LIMIT {
+                    // Back off further if comms were lost again following reinitialisation
+                    if comms_lost {
+                        backoff = (backoff * 2).min(COMMS_BACKOFF_MAX);
+                    }
+
+                    warn!(
+                        "Comms lost after {} consecutive tick errors, reinitialising engine in {:?}",
+                        tick_errors, backoff
+                    );
+
+                    engine = None;
+                    comms_lost = true;
+                    tick_errors = 0;
+                    retry_at = Instant::now() + backoff;
+                }
+            }
+            None => (),
+        }
+
+        // If we're not yet due for a measurement, sleep and continue
+        let now = Instant::now();
+        if matches!(last, Some(l) if now.duration_since(l) < period) {
+            std::thread::sleep(Duration::from_millis(100));
+            continue;
+        }
+        last = Some(now);
+
+        // Reload configuration on change
+        let m = modified(&args.config);
+        if m != cfg_modified {
+            cfg_modified = m;
+
+            // Period, driver and differential publishing changes apply live,
+            // service configuration changes are retained until restart
+            match DeviceConfig::load(&args.config) {
+                Ok(c) => match (c.period(), driver(&c.driver)) {
+                    (Ok(p), Ok(d)) => {
+                        info!("Reloaded configuration");
+                        if cfg.requires_restart(&c) {
+                            warn!("Service configuration changed, restart required to apply");
+                        }
+
+                        if c.differential != cfg.differential {
+                            published = None;
+                        }
+
+                        period = p;
+                        sensor = d;
+                        cfg.period = c.period;
+                        cfg.driver = c.driver;
+                        cfg.differential = c.differential;
+                    }
+                    (Err(e), _) | (_, Err(e)) => error!("Failed to apply configuration: {:?}", e),
+                },
+                Err(e) => error!("Failed to reload configuration: {:?}", e),
+            }
+        }
+
+        // Take measurement
+        let mut values = match sensor.read() {
+            Ok(v) => v,
+            Err(e) => {
+                error!("Sensor read error: {:?}", e);
+                if let Some(d) = diagnostics.as_mut() {
+                    d.record_error();
+                }
+                continue;
+            }
+        };
+
+        if values.len() != cfg.endpoints.len() {
+            warn!(
+                "Driver returned {} values for {} endpoints",
+                values.len(),
+                cfg.endpoints.len()
+            );
+        }
+
+        // Apply device-side calibration to raw driver values
+        for (v, e) in values.iter_mut().zip(&cfg.endpoints) {
+            if e.calibrate_on_device {
+                v.value = e.calibration.apply(&v.value);
+            }
+        }
+
+        // Compute derived rates, values without a rate (the first reading, or
+        // missing sources) retain their position but are not marked as good
+        let t = now.duration_since(start).as_millis() as u64;
+        for (source, r) in rates.iter_mut() {
+            let d = match values.get(*source).map(|v| r.update(&v.value, t)) {
+                Some(Some(rate)) => EpData::new(rate.into()),
+                Some(None) => EpData::new(0f32.into()).with_quality(EpQuality::Uncertain),
+                None => EpData::new(0f32.into()).with_quality(EpQuality::Bad),
+            };
+            values.push(d);
+        }
+
+        let mut data = match IotData::new(&values) {
+            Ok(d) => d,
+            Err(_) => {
+                error!("Too many values from driver: {}", values.len());
+                continue;
+            }
+        };
+
+        // Append diagnostics where due, these must follow all driver and derived values
+        if let Some(d) = diagnostics.as_mut() {
+            if values.len() == cfg.endpoints.len() + cfg.derived.len() {
+                if let Err(e) = d.append(&mut data, t) {
+                    error!("Failed to append diagnostics: {:?}", e);
+                }
+            }
+        }
+
+        // Publish only changed endpoints where enabled, with periodic full objects.
+        // Differential state is only updated once an object has been published,
+        // so deltas are always relative to values subscribers have received.
+        let next: Vec<_> = data.data.iter().cloned().collect();
+        let mut full = true;
+
+        if let (Some(full_every), Some(prev)) = (cfg.differential, &published) {
+            if since_full + 1 < full_every {
+                match delta(prev, &next) {
+                    Ok(d) => {
+                        full = !is_delta(&d.data);
+                        data = d;
+                    }
+                    Err(e) => error!("Failed to build differential object: {:?}", e),
+                }
+            }
+        }
+
+        debug!("Measurement: {:?}", data);
+
+        // Publish new object
+        let engine = match engine.as_mut() {
+            Some(e) => e,
+            None => {
+                warn!("Comms unavailable, skipping publish");
+                if let Some(d) = diagnostics.as_mut() {
+                    d.record_error();
+                }
+                // Force a full object once comms are restored
+                published = None;
+                continue;
+            }
+        };
+
+        match engine.publish(data, &[]) {
+            Ok(sig) => {
+                info!("Published object: {:#}", sig);
+
+                if cfg.differential.is_some() {
+                    since_full = if full { 0 } else { since_full + 1 };
+                    published = Some(next);
+                }
+            }
+            Err(e) => {
+                error!("Failed to publish object: {:?}", e);
+                if let Some(d) = diagnostics.as_mut() {
+                    d.record_error();
+                }
+                // Force a full object following publish failures
+                published = None;
+            }
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! Service control access summaries, reporting which endpoints may be
+//! controlled and which identity is permitted to do so.
+//!
+//! Control messages are authorised by the service owner's keys, so only
+//! clients holding the service private key may issue control commands.
+
+use core::fmt::Display;
+
+use dsf_core::prelude::MaybeEncrypted;
+use dsf_rpc::{DataInfo, ServiceInfo};
+
+use crate::endpoint::{EpDescriptor, EpFlags, EpKind, EpRole};
+
+/// Control access summary for a service
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessSummary {
+    /// Service is owned by this client (private key available)
+    pub owned: bool,
+    /// Service data is readable by this client (public or secret key available)
+    pub readable: bool,
+    /// Writable endpoints (index, kind and role)
+    pub writable: Vec<(usize, EpKind, EpRole)>,
+}
+
+impl AccessSummary {
+    /// Build an access summary from ownership, readability and endpoint descriptors
+    pub fn new(owned: bool, readable: bool, descriptors: &[EpDescriptor]) -> Self {
+        let writable = descriptors
+            .iter()
+            .enumerate()
+            .filter(|(_i, d)| d.flags.contains(EpFlags::W))
+            .map(|(i, d)| (i, d.kind, d.role()))
+            .collect();
+
+        Self {
+            owned,
+            readable,
+            writable,
+        }
+    }
+
+    /// Build an access summary for a service and primary page
+    pub fn from_service(info: &ServiceInfo, page: &DataInfo<Vec<EpDescriptor>>) -> Self {
+        let (readable, descriptors) = match &page.body {
+            MaybeEncrypted::Cleartext(d) => (true, &d[..]),
+            MaybeEncrypted::Encrypted(_) => (false, &[][..]),
+            MaybeEncrypted::None => (true, &[][..]),
+        };
+
+        Self::new(info.private_key.is_some(), readable, descriptors)
+    }
+
+    /// Check whether this client may control the service
+    pub fn controllable(&self) -> bool {
+        self.owned && !self.writable.is_empty()
+    }
+}
+
+impl Display for AccessSummary {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match (self.readable, self.owned) {
+            (false, _) => return write!(f, "unknown (encrypted, secret key required)"),
+            (true, _) if self.writable.is_empty() => return write!(f, "read-only"),
+            (true, true) => write!(f, "owner (this client) may control ")?,
+            (true, false) => write!(f, "owner only may control ")?,
+        }
+
+        for (n, (i, k, r)) in self.writable.iter().enumerate() {
+            if n > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}: {} ({})", i, k, r)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn access_summaries() {
+        let descriptors = [
+            EpDescriptor::new(EpKind::Temperature, EpFlags::R),
+            EpDescriptor::new(EpKind::State, EpFlags::RW),
+            EpDescriptor::new(EpKind::Brightness, EpFlags::RW).with_role(EpRole::Config),
+        ];
+
+        let a = AccessSummary::new(true, true, &descriptors);
+        assert!(a.controllable());
+        assert_eq!(
+            a.to_string(),
+            "owner (this client) may control 1: state (actuator), 2: brightness (config)"
+        );
+
+        let a = AccessSummary::new(false, true, &descriptors);
+        assert!(!a.controllable());
+        assert_eq!(
+            a.to_string(),
+            "owner only may control 1: state (actuator), 2: brightness (config)"
+        );
+
+        let a = AccessSummary::new(true, true, &descriptors[..1]);
+        assert!(!a.controllable());
+        assert_eq!(a.to_string(), "read-only");
+
+        let a = AccessSummary::new(false, false, &[]);
+        assert_eq!(a.to_string(), "unknown (encrypted, secret key required)");
+    }
+}
@@ -0,0 +1,251 @@
+//! Service history audit, walking stored objects to report invalid object
+//! signatures, broken previous-signature links, missing objects, and
+//! conflicting objects sharing an index.
+//!
+//! Signatures are verified against the service public key by the caller
+//! (see [`crate::client::IotClient::audit`]), this module checks the
+//! resulting history.
+
+use core::fmt::Display;
+
+/// Stored object to be audited
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditItem<S> {
+    /// Object index
+    pub index: u64,
+    /// Object signature
+    pub signature: S,
+    /// Signature of the preceding object, as linked by this object
+    pub previous: Option<S>,
+    /// Whether the object signature is valid for the service public key
+    pub verified: bool,
+}
+
+/// Issue located in a service history
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuditIssue<S> {
+    /// Object signature is not valid for the service public key
+    InvalidSignature { index: u64, signature: S },
+    /// Object does not link to the signature of the preceding object
+    BrokenLink {
+        index: u64,
+        expected: S,
+        found: Option<S>,
+    },
+    /// Missing objects between (exclusive) indices
+    Gap { after: u64, before: u64 },
+    /// Differing objects with the same index, indicating tampering or a forked history
+    Conflict { index: u64, first: S, second: S },
+}
+
+/// Service history audit report
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditReport<S> {
+    /// Number of objects audited
+    pub objects: usize,
+    /// Lowest object index
+    pub first: Option<u64>,
+    /// Highest object index
+    pub last: Option<u64>,
+    /// Located issues
+    pub issues: Vec<AuditIssue<S>>,
+}
+
+impl<S> AuditReport<S> {
+    /// Check whether the audited history is complete and consistent
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Audit a service history from objects in any order
+pub fn audit<S: Clone + PartialEq>(
+    items: impl IntoIterator<Item = AuditItem<S>>,
+) -> AuditReport<S> {
+    let mut items: Vec<_> = items.into_iter().collect();
+    items.sort_by_key(|i| i.index);
+
+    // Identical objects may be returned more than once
+    items.dedup_by(|b, a| a.index == b.index && a.signature == b.signature);
+
+    let mut issues: Vec<_> = items
+        .iter()
+        .filter(|i| !i.verified)
+        .map(|i| AuditIssue::InvalidSignature {
+            index: i.index,
+            signature: i.signature.clone(),
+        })
+        .collect();
+
+    for w in items.windows(2) {
+        let (a, b) = (&w[0], &w[1]);
+
+        if a.index == b.index {
+            issues.push(AuditIssue::Conflict {
+                index: a.index,
+                first: a.signature.clone(),
+                second: b.signature.clone(),
+            });
+        } else if b.index - a.index > 1 {
+            issues.push(AuditIssue::Gap {
+                after: a.index,
+                before: b.index,
+            });
+        } else if b.previous.as_ref() != Some(&a.signature) {
+            issues.push(AuditIssue::BrokenLink {
+                index: b.index,
+                expected: a.signature.clone(),
+                found: b.previous.clone(),
+            });
+        }
+    }
+
+    AuditReport {
+        objects: items.len(),
+        first: items.first().map(|i| i.index),
+        last: items.last().map(|i| i.index),
+        issues,
+    }
+}
+
+impl<S: Display> Display for AuditReport<S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match (self.first, self.last) {
+            (Some(a), Some(b)) => {
+                writeln!(f, "Audited {} objects (index {} to {})", self.objects, a, b)?
+            }
+            _ => writeln!(f, "No objects found")?,
+        }
+
+        for i in &self.issues {
+            match i {
+                AuditIssue::InvalidSignature { index, signature } => writeln!(
+                    f,
+                    "  - invalid signature at index {}: {:#}",
+                    index, signature
+                )?,
+                AuditIssue::BrokenLink {
+                    index,
+                    expected,
+                    found: Some(found),
+                } => writeln!(
+                    f,
+                    "  - broken link at index {}: expected previous {:#}, found {:#}",
+                    index, expected, found
+                )?,
+                AuditIssue::BrokenLink {
+                    index, expected, ..
+                } => writeln!(
+                    f,
+                    "  - broken link at index {}: expected previous {:#}, found none",
+                    index, expected
+                )?,
+                AuditIssue::Gap { after, before } => writeln!(
+                    f,
+                    "  - missing objects between index {} and {}",
+                    after, before
+                )?,
+                AuditIssue::Conflict {
+                    index,
+                    first,
+                    second,
+                } => writeln!(
+                    f,
+                    "  - conflicting objects at index {}: {:#} / {:#}",
+                    index, first, second
+                )?,
+            }
+        }
+
+        match self.is_ok() {
+            true => write!(f, "History OK"),
+            false => write!(f, "History has {} issue(s)", self.issues.len()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a linked history of valid objects
+    fn history(sigs: &[&'static str]) -> Vec<AuditItem<&'static str>> {
+        sigs.iter()
+            .enumerate()
+            .map(|(i, s)| AuditItem {
+                index: i as u64,
+                signature: *s,
+                previous: i.checked_sub(1).map(|p| sigs[p]),
+                verified: true,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn audit_history() {
+        let mut h = history(&["a", "b", "c"]);
+        h.reverse();
+        let r = audit(h);
+        assert!(r.is_ok());
+        assert_eq!((r.first, r.last, r.objects), (Some(0), Some(2), 3));
+
+        // Duplicate (identical) objects are not issues
+        let h = history(&["a"]);
+        assert!(audit(h.iter().chain(&h).cloned()).is_ok());
+
+        // Conflicting objects and gaps
+        let mut h = history(&["a", "b", "c", "d", "e"]);
+        h[2].index = 1;
+        h[2].signature = "x";
+        h.remove(3);
+        let r = audit(h);
+        assert_eq!(
+            r.issues,
+            vec![
+                AuditIssue::Conflict {
+                    index: 1,
+                    first: "b",
+                    second: "x"
+                },
+                AuditIssue::Gap {
+                    after: 1,
+                    before: 4
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn audit_signatures_links() {
+        // Forged object, with a valid-looking link but invalid signature
+        let mut h = history(&["a", "b", "c"]);
+        h[1].verified = false;
+        assert_eq!(
+            audit(h).issues,
+            vec![AuditIssue::InvalidSignature {
+                index: 1,
+                signature: "b"
+            }]
+        );
+
+        // Replaced object, breaking the link from the following object
+        let mut h = history(&["a", "b", "c"]);
+        h[1].signature = "x";
+        h[1].previous = None;
+        assert_eq!(
+            audit(h).issues,
+            vec![
+                AuditIssue::BrokenLink {
+                    index: 1,
+                    expected: "a",
+                    found: None
+                },
+                AuditIssue::BrokenLink {
+                    index: 2,
+                    expected: "x",
+                    found: Some("b")
+                },
+            ]
+        );
+    }
+}
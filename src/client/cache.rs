@@ -0,0 +1,196 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+use dsf_core::options::Options;
+use dsf_core::types::Id;
+use dsf_rpc::{DataInfo, ServiceIdentifier, ServiceInfo};
+
+use crate::error::IotError;
+use crate::prelude::{EpData, EpDescriptor};
+
+/// Maximum number of data objects cached per service
+pub const MAX_CACHED_DATA: usize = 64;
+
+/// Minimum interval between cache writes for long-running commands
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Local on-disk cache of service information, descriptors, and data,
+/// allowing services to be browsed when the daemon is unavailable.
+///
+/// Changes are written periodically (see [`ClientCache::save_periodic`])
+/// and when the cache is dropped.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ClientCache {
+    #[serde(skip)]
+    path: PathBuf,
+
+    #[serde(skip)]
+    dirty: bool,
+
+    #[serde(skip)]
+    saved: Option<Instant>,
+
+    services: Vec<CachedService>,
+}
+
+/// Cached service entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedService {
+    /// Service information
+    pub info: ServiceInfo,
+
+    /// Primary page containing endpoint descriptors
+    pub page: DataInfo<Vec<EpDescriptor>>,
+
+    /// Data objects retrieved for the service
+    #[serde(default)]
+    pub data: Vec<DataInfo<Vec<EpData>>>,
+}
+
+impl ClientCache {
+    /// Load a cache from the provided file, creating an empty cache if the file does not exist
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, IotError> {
+        let path = path.as_ref().to_path_buf();
+
+        let mut cache = match path.exists() {
+            true => {
+                let s = std::fs::read_to_string(&path)?;
+                serde_json::from_str(&s)?
+            }
+            false => ClientCache::default(),
+        };
+
+        debug!(
+            "Loaded {} cached services from {:?}",
+            cache.services.len(),
+            path
+        );
+
+        let n = cache.expire(SystemTime::now());
+        if n > 0 {
+            debug!("Dropped {} expired data objects", n);
+        }
+
+        cache.path = path;
+        cache.dirty = n > 0;
+        cache.saved = Some(Instant::now());
+
+        Ok(cache)
+    }
+
+    /// Write the cache to disk
+    pub fn save(&mut self) -> Result<(), IotError> {
+        if let Some(p) = self.path.parent() {
+            std::fs::create_dir_all(p)?;
+        }
+
+        let s = serde_json::to_string(self)?;
+        std::fs::write(&self.path, s)?;
+
+        self.dirty = false;
+        self.saved = Some(Instant::now());
+
+        Ok(())
+    }
+
+    /// Write the cache to disk if changed and not written within the save interval
+    pub fn save_periodic(&mut self) -> Result<(), IotError> {
+        match (self.dirty, self.saved) {
+            (true, Some(t)) if t.elapsed() < SAVE_INTERVAL => Ok(()),
+            (true, _) => self.save(),
+            (false, _) => Ok(()),
+        }
+    }
+
+    /// Update cached information for a service
+    pub fn update_service(&mut self, info: &ServiceInfo, page: &DataInfo<Vec<EpDescriptor>>) {
+        self.dirty = true;
+
+        match self.services.iter_mut().find(|s| s.info.id == info.id) {
+            Some(s) => {
+                s.info = info.clone();
+                s.page = page.clone();
+            }
+            None => self.services.push(CachedService {
+                info: info.clone(),
+                page: page.clone(),
+                data: vec![],
+            }),
+        }
+    }
+
+    /// Update cached data for a service, ignoring objects already in the cache
+    /// and retaining up to [`MAX_CACHED_DATA`] of the newest objects
+    pub fn update_data(&mut self, id: &Id, data: &[DataInfo<Vec<EpData>>]) {
+        let s = match self.services.iter_mut().find(|s| &s.info.id == id) {
+            Some(s) => s,
+            None => return,
+        };
+
+        let now = SystemTime::now();
+        for d in data {
+            if !expired(d, now) && !s.data.iter().any(|c| c.signature == d.signature) {
+                s.data.push(d.clone());
+                self.dirty = true;
+            }
+        }
+
+        s.data.sort_by(|a, b| b.index.cmp(&a.index));
+        s.data.truncate(MAX_CACHED_DATA);
+    }
+
+    /// Drop cached data objects that have expired at `now`, returning the number removed
+    pub fn expire(&mut self, now: SystemTime) -> usize {
+        let mut n = 0;
+
+        for s in &mut self.services {
+            let len = s.data.len();
+            s.data.retain(|d| !expired(d, now));
+            n += len - s.data.len();
+        }
+
+        n
+    }
+
+    /// List cached services
+    pub fn list(&self) -> Vec<(ServiceInfo, DataInfo<Vec<EpDescriptor>>)> {
+        self.services
+            .iter()
+            .map(|s| (s.info.clone(), s.page.clone()))
+            .collect()
+    }
+
+    /// Find a cached service by ID.
+    ///
+    /// Cache order does not correspond with daemon service indices, so
+    /// services identified by index are not matched.
+    pub fn find(&self, service: &ServiceIdentifier) -> Option<&CachedService> {
+        let id = service.id.as_ref()?;
+        self.services.iter().find(|s| &s.info.id == id)
+    }
+}
+
+impl Drop for ClientCache {
+    fn drop(&mut self) {
+        if !self.dirty {
+            return;
+        }
+
+        if let Err(e) = self.save() {
+            warn!("Failed to write cache: {:?}", e);
+        }
+    }
+}
+
+/// Check whether a data object has expired at `now`, using the object `Expiry` option.
+///
+/// Objects without an expiry do not expire.
+pub fn expired(data: &DataInfo<Vec<EpData>>, now: SystemTime) -> bool {
+    data.public_options.iter().any(|o| match o {
+        Options::Expiry(t) => SystemTime::from(*t) <= now,
+        _ => false,
+    })
+}
@@ -0,0 +1,270 @@
+//! Composite (virtual) services, combining endpoints from several underlying
+//! services into a single published service.
+
+use core::str::FromStr;
+
+use futures::{prelude::*, stream};
+use log::{debug, warn};
+
+use dsf_core::prelude::*;
+use dsf_rpc::ServiceIdentifier;
+
+use super::{IotClient, PublishOptions};
+use crate::endpoint::{
+    parse_endpoint_kind, EpData, EpDescriptor, EpFlags, EpKind, EpReadings, MAX_ENDPOINTS,
+};
+use crate::error::IotError;
+
+/// Source service and selected endpoints for a composite service
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompositeSource {
+    /// Source service ID
+    pub id: Id,
+    /// Endpoints to be included from the source service
+    pub endpoints: Vec<EpKind>,
+}
+
+/// Parse a composite source in the form `ID=KIND[,KIND...]`
+pub fn parse_composite_source(src: &str) -> Result<CompositeSource, IotError> {
+    let (id, eps) = src.split_once('=').ok_or(IotError::InvalidSource)?;
+
+    let id = Id::from_str(id).map_err(|_| IotError::InvalidSource)?;
+    let endpoints = eps
+        .split(',')
+        .map(parse_endpoint_kind)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(CompositeSource { id, endpoints })
+}
+
+impl FromStr for CompositeSource {
+    type Err = IotError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_composite_source(s)
+    }
+}
+
+/// Composite service state, tracking the latest value for each source endpoint
+#[derive(Debug, Clone)]
+pub struct Composite {
+    sources: Vec<CompositeSource>,
+    values: Vec<Vec<Option<EpData>>>,
+}
+
+impl Composite {
+    /// Create a composite service from the provided sources
+    pub fn new(sources: Vec<CompositeSource>) -> Self {
+        let values = sources
+            .iter()
+            .map(|s| vec![None; s.endpoints.len()])
+            .collect();
+
+        Self { sources, values }
+    }
+
+    /// Fetch composite sources
+    pub fn sources(&self) -> &[CompositeSource] {
+        &self.sources
+    }
+
+    /// Build endpoint descriptors for the composite service, in source order
+    pub fn descriptors(&self) -> Vec<EpDescriptor> {
+        self.sources
+            .iter()
+            .flat_map(|s| s.endpoints.iter())
+            .map(|k| EpDescriptor::new(*k, EpFlags::R))
+            .collect()
+    }
+
+    /// Check the composite service endpoints fit a default IoT service,
+    /// prior to creating or publishing to the composite service
+    pub fn validate(&self) -> Result<(), IotError> {
+        let count = self.sources.iter().map(|s| s.endpoints.len()).sum();
+
+        match count {
+            0 => Err(IotError::EmptyData),
+            n if n > MAX_ENDPOINTS => Err(IotError::TooManyEndpoints {
+                count: n,
+                max: MAX_ENDPOINTS,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Update values for a source from its descriptors and latest data,
+    /// returning the number of endpoints updated
+    pub fn update(
+        &mut self,
+        source: usize,
+        descriptors: &[EpDescriptor],
+        data: &[EpData],
+    ) -> usize {
+        let (s, values) = match (self.sources.get(source), self.values.get_mut(source)) {
+            (Some(s), Some(v)) => (s, v),
+            _ => return 0,
+        };
+
+        let readings = EpReadings::new(descriptors, data);
+        let mut n = 0;
+
+        for (i, k) in s.endpoints.iter().enumerate() {
+            // Repeated kinds select successive endpoints of that kind
+            let nth = s.endpoints[..i].iter().filter(|e| *e == k).count();
+
+            if let Some(v) = readings.value_nth(*k, nth) {
+                values[i] = Some(EpData::new(v.clone()));
+                n += 1;
+            }
+        }
+
+        n
+    }
+
+    /// Fetch combined data, once values are available for all endpoints
+    pub fn data(&self) -> Option<Vec<EpData>> {
+        self.values.iter().flat_map(|v| v.iter()).cloned().collect()
+    }
+}
+
+/// Run a composite service, republishing combined data to `target` on source updates.
+/// A separate client connection is used for subscriptions (as with watch).
+///
+/// Query and publish failures are logged and do not stop the composite service.
+pub async fn run(
+    client: &mut IotClient,
+    sub: &mut IotClient,
+    target: ServiceIdentifier,
+    mut composite: Composite,
+) -> Result<(), IotError> {
+    composite.validate()?;
+
+    // Subscribe to source services
+    let services: Vec<_> = composite
+        .sources()
+        .iter()
+        .map(|s| ServiceIdentifier::id(s.id.clone()))
+        .collect();
+    let updates = sub.subscribe_all(&services).await?;
+
+    // Load initial values, then update on notification
+    let initial = stream::iter(0..services.len());
+    let mut updates = Box::pin(initial.chain(updates));
+
+    while let Some(i) = updates.next().await {
+        let id = composite.sources()[i].id.clone();
+
+        let latest = async {
+            let mut h = client.handle(ServiceIdentifier::id(id.clone())).await?;
+            let eps = h.descriptors().map(|e| e.to_vec());
+            h.latest().await.map(|d| (eps, d))
+        };
+
+        let (eps, latest) = match latest.await {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("Failed to fetch data for source {:#}: {:?}", id, e);
+                continue;
+            }
+        };
+
+        let (eps, values) = match (eps, latest.map(|d| d.body)) {
+            (Some(e), Some(MaybeEncrypted::Cleartext(v))) => (e, v),
+            _ => {
+                warn!("No data available for source {:#}", id);
+                continue;
+            }
+        };
+
+        if composite.update(i, &eps, &values) != composite.sources()[i].endpoints.len() {
+            warn!("Missing endpoints for source {:#}", id);
+        }
+
+        // Publish once all endpoint values are available
+        if let Some(data) = composite.data() {
+            debug!("Publishing composite data: {:?}", data);
+
+            let res = client
+                .publish(PublishOptions {
+                    service: target.clone(),
+                    data,
+                    meta: vec![],
+                    max_endpoints: MAX_ENDPOINTS,
+                })
+                .await;
+
+            if let Err(e) = res {
+                warn!("Failed to publish composite data: {:?}", e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn composite_update() {
+        let a = Id::from([1u8; 32]);
+        let b = Id::from([2u8; 32]);
+
+        let mut c = Composite::new(vec![
+            CompositeSource {
+                id: a,
+                endpoints: vec![EpKind::Temperature],
+            },
+            CompositeSource {
+                id: b,
+                endpoints: vec![EpKind::Humidity, EpKind::Pressure],
+            },
+        ]);
+
+        assert_eq!(c.descriptors().len(), 3);
+        assert!(c.validate().is_ok());
+        assert_eq!(c.descriptors()[1].kind, EpKind::Humidity);
+
+        let eps = [
+            EpDescriptor::new(EpKind::Pressure, EpFlags::R),
+            EpDescriptor::new(EpKind::Humidity, EpFlags::R),
+        ];
+        let data = [EpData::new(101.2.into()), EpData::new(55.0.into())];
+        assert_eq!(c.update(1, &eps, &data), 2);
+        assert_eq!(c.data(), None);
+
+        let eps = [EpDescriptor::new(EpKind::Temperature, EpFlags::R)];
+        let data = [EpData::new(21.5.into())];
+        assert_eq!(c.update(0, &eps, &data), 1);
+
+        assert_eq!(
+            c.data(),
+            Some(vec![
+                EpData::new(21.5.into()),
+                EpData::new(55.0.into()),
+                EpData::new(101.2.into()),
+            ])
+        );
+    }
+
+    #[test]
+    fn composite_validation() {
+        let source = |n| CompositeSource {
+            id: Id::from([1u8; 32]),
+            endpoints: vec![EpKind::Temperature; n],
+        };
+
+        assert!(matches!(
+            Composite::new(vec![]).validate(),
+            Err(IotError::EmptyData)
+        ));
+        assert!(Composite::new(vec![source(4), source(4)])
+            .validate()
+            .is_ok());
+        assert!(matches!(
+            Composite::new(vec![source(4), source(5)]).validate(),
+            Err(IotError::TooManyEndpoints { count: 9, max: 8 })
+        ));
+    }
+}
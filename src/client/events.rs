@@ -0,0 +1,100 @@
+//! Client-side helpers for [`EpKind::Event`](crate::endpoint::EpKind::Event)
+//! endpoints, deduplicating repeated observations of event values and
+//! latching events until acknowledged.
+//!
+//! Event endpoints carry a monotonic counter rather than a level, so new
+//! events are detected from counter changes and events occurring between
+//! observations are counted rather than lost.
+
+use crate::endpoint::EpValue;
+use crate::error::IotError;
+
+/// Event endpoint tracker
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct EventTracker {
+    last: Option<(u64, u64)>,
+    latched: u64,
+}
+
+impl EventTracker {
+    /// Create a new event tracker
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Update the tracker with an observed event value, returning the number
+    /// of new events since the previous observation.
+    ///
+    /// The first observation establishes the baseline and repeated observations
+    /// of the same value report no new events. A counter reset (e.g. on device
+    /// restart) reports the new counter value.
+    pub fn update(&mut self, v: &EpValue) -> Result<u64, IotError> {
+        let (count, time_ms) = match v {
+            EpValue::Event { count, time_ms } => (*count, *time_ms),
+            _ => return Err(IotError::MismatchedType),
+        };
+
+        let n = match self.last {
+            None => 0,
+            Some((c, _t)) if count >= c => count - c,
+            Some((_c, t)) if time_ms > t => count,
+            // Stale observation, ignore
+            Some(_) => return Ok(0),
+        };
+
+        self.last = Some((count, time_ms));
+        self.latched += n;
+
+        Ok(n)
+    }
+
+    /// Time of the most recent observed event in milliseconds since the Unix epoch
+    pub fn time_ms(&self) -> Option<u64> {
+        self.last.map(|(_c, t)| t)
+    }
+
+    /// Check whether events have been latched since the last acknowledgement
+    pub fn latched(&self) -> bool {
+        self.latched > 0
+    }
+
+    /// Acknowledge latched events, returning the number of events latched
+    pub fn acknowledge(&mut self) -> u64 {
+        core::mem::take(&mut self.latched)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(count: u64, time_ms: u64) -> EpValue {
+        EpValue::Event { count, time_ms }
+    }
+
+    #[test]
+    fn event_tracking() {
+        let mut t = EventTracker::new();
+
+        // First observation sets the baseline
+        assert_eq!(t.update(&event(5, 1_000)).unwrap(), 0);
+        assert!(!t.latched());
+
+        // Duplicates are ignored, gaps are counted
+        assert_eq!(t.update(&event(6, 2_000)).unwrap(), 1);
+        assert_eq!(t.update(&event(6, 2_000)).unwrap(), 0);
+        assert_eq!(t.update(&event(9, 5_000)).unwrap(), 3);
+        assert_eq!(t.time_ms(), Some(5_000));
+
+        // Events remain latched until acknowledged
+        assert!(t.latched());
+        assert_eq!(t.acknowledge(), 4);
+        assert!(!t.latched());
+
+        // Stale values are ignored, counter resets report new events
+        assert_eq!(t.update(&event(8, 4_000)).unwrap(), 0);
+        assert_eq!(t.update(&event(2, 7_000)).unwrap(), 2);
+
+        assert!(t.update(&EpValue::Bool(true)).is_err());
+    }
+}
@@ -0,0 +1,190 @@
+//! Parallel service information fetching for multi-service commands, with
+//! bounded concurrency and per-service failure reporting.
+
+use core::fmt::Display;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use futures::future;
+use log::debug;
+
+use dsf_core::prelude::*;
+use dsf_rpc::{self as rpc, DataInfo, ServiceInfo};
+
+use super::{cached_page, Client, Error, InfoOptions, PageCache, ServiceIdentifier};
+use crate::endpoint::EpDescriptor;
+use crate::error::IotError;
+
+/// Default number of concurrent daemon connections for multi-service fetches
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Progress callback for multi-service fetches, called with the number of
+/// completed and total services as each service completes
+pub type FetchProgress = fn(usize, usize);
+
+/// Service to be fetched, by ID or with already known service information
+#[derive(Debug, Clone)]
+pub enum FetchTarget {
+    /// Service ID, information is requested from the daemon
+    Id(Id),
+    /// Known service information (e.g. from a service list), only the page is fetched
+    Info(ServiceInfo),
+}
+
+impl FetchTarget {
+    /// Fetch the ID of the target service
+    pub fn id(&self) -> &Id {
+        match self {
+            FetchTarget::Id(id) => id,
+            FetchTarget::Info(info) => &info.id,
+        }
+    }
+}
+
+impl From<Id> for FetchTarget {
+    fn from(id: Id) -> Self {
+        FetchTarget::Id(id)
+    }
+}
+
+impl From<ServiceInfo> for FetchTarget {
+    fn from(info: ServiceInfo) -> Self {
+        FetchTarget::Info(info)
+    }
+}
+
+/// Failure fetching information for a service
+#[derive(Debug)]
+pub struct FetchFailure {
+    pub id: Id,
+    pub error: IotError,
+}
+
+/// Multi-service fetch results, with services in request order
+#[derive(Debug)]
+pub struct FetchReport {
+    pub services: Vec<(ServiceInfo, DataInfo<Vec<EpDescriptor>>)>,
+    pub failures: Vec<FetchFailure>,
+}
+
+impl FetchReport {
+    /// Check whether all services were fetched
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl Display for FetchReport {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "Fetched {} of {} services",
+            self.services.len(),
+            self.services.len() + self.failures.len()
+        )?;
+
+        for e in &self.failures {
+            write!(f, "\n  - {:#}: {}", e.id, e.error)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Fetch service information (where not already known) and endpoints,
+/// using cached pages where available
+pub(crate) async fn fetch_info(
+    client: &mut Client,
+    pages: &PageCache,
+    target: FetchTarget,
+) -> Result<(ServiceInfo, DataInfo<Vec<EpDescriptor>>), IotError> {
+    let info = match target {
+        FetchTarget::Info(info) => info,
+        FetchTarget::Id(id) => {
+            let (_h, info) = client
+                .info(InfoOptions {
+                    service: ServiceIdentifier::id(id),
+                })
+                .await?;
+            info
+        }
+    };
+
+    // Check we have a primary page object
+    let page_sig = match &info.primary_page {
+        Some(s) => s.clone(),
+        None => return Err(IotError::Client(Error::NoPageFound)),
+    };
+
+    if let Some(p) = cached_page(pages, &info) {
+        return Ok((info, p.clone()));
+    }
+
+    // Lookup page object and parse using iot application body
+    let (page_info, _page) = client
+        .object(rpc::FetchOptions {
+            service: info.id.clone().into(),
+            page_sig,
+        })
+        .await?;
+    let page_info = page_info.convert::<Vec<EpDescriptor>>()?;
+
+    Ok((info, page_info))
+}
+
+/// Fetch information for the provided services, distributing requests
+/// across the provided client connections and reporting progress as each
+/// service completes
+pub(crate) async fn fetch_all(
+    clients: Vec<&mut Client>,
+    pages: &PageCache,
+    targets: Vec<FetchTarget>,
+    progress: Option<FetchProgress>,
+) -> FetchReport {
+    let n = clients.len().max(1);
+    let total = targets.len();
+    let done = AtomicUsize::new(0);
+
+    // Assign services to connections, retaining request order
+    let mut jobs: Vec<Vec<(usize, FetchTarget)>> = (0..n).map(|_| vec![]).collect();
+    for (i, t) in targets.into_iter().enumerate() {
+        jobs[i % n].push((i, t));
+    }
+
+    debug!("Fetching {} services using {} connection(s)", total, n);
+
+    let done = &done;
+    let workers = clients.into_iter().zip(jobs).map(|(c, jobs)| async move {
+        let mut results = Vec::with_capacity(jobs.len());
+        for (i, t) in jobs {
+            let id = t.id().clone();
+            let r = fetch_info(c, pages, t).await;
+            results.push((i, id, r));
+
+            let n = done.fetch_add(1, Ordering::Relaxed) + 1;
+            if let Some(p) = progress {
+                p(n, total);
+            }
+        }
+        results
+    });
+
+    let mut results: Vec<_> = future::join_all(workers)
+        .await
+        .into_iter()
+        .flatten()
+        .collect();
+    results.sort_by_key(|(i, _id, _r)| *i);
+
+    let mut report = FetchReport {
+        services: vec![],
+        failures: vec![],
+    };
+    for (_i, id, r) in results {
+        match r {
+            Ok(s) => report.services.push(s),
+            Err(error) => report.failures.push(FetchFailure { id, error }),
+        }
+    }
+
+    report
+}
@@ -0,0 +1,211 @@
+//! Service location helpers, for publishing coordinates with services and
+//! filtering discovered services by distance.
+//!
+//! Locations are read from `Coord` page options (as published by devices)
+//! or from `lat` / `lng` / `alt` service metadata (as set via `create --location`).
+
+use core::fmt::Display;
+use core::str::FromStr;
+
+use dsf_core::options::{Coordinates, Options};
+use dsf_core::prelude::MaybeEncrypted;
+use dsf_rpc::DataInfo;
+
+use crate::endpoint::EpDescriptor;
+use crate::error::IotError;
+
+/// Mean earth radius in kilometres
+const EARTH_RADIUS_KM: f32 = 6371.0;
+
+/// Metadata keys used for service locations
+pub const LOCATION_KEYS: [&str; 3] = ["lat", "lng", "alt"];
+
+/// Service location in decimal degrees, with optional altitude in metres
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Location {
+    pub lat: f32,
+    pub lng: f32,
+    pub alt: Option<f32>,
+}
+
+impl Location {
+    /// Create a new location, checking latitude and longitude bounds
+    pub fn new(lat: f32, lng: f32, alt: Option<f32>) -> Result<Self, IotError> {
+        if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lng) {
+            return Err(IotError::InvalidLocation);
+        }
+
+        Ok(Self { lat, lng, alt })
+    }
+
+    /// Great-circle (haversine) distance to another location in kilometres
+    pub fn distance_km(&self, other: &Location) -> f32 {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let d_lat = lat2 - lat1;
+        let d_lng = (other.lng - self.lng).to_radians();
+
+        let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lng / 2.0).sin().powi(2);
+
+        2.0 * EARTH_RADIUS_KM * a.sqrt().min(1.0).asin()
+    }
+
+    /// Encode location as service metadata key-value pairs
+    pub fn metadata(&self) -> Vec<(String, String)> {
+        let mut m = vec![
+            (LOCATION_KEYS[0].to_string(), self.lat.to_string()),
+            (LOCATION_KEYS[1].to_string(), self.lng.to_string()),
+        ];
+        if let Some(alt) = self.alt {
+            m.push((LOCATION_KEYS[2].to_string(), alt.to_string()));
+        }
+        m
+    }
+
+    /// Encode location as a page option
+    pub fn option(&self) -> Options {
+        Options::Coord(Coordinates {
+            lat: self.lat,
+            lng: self.lng,
+            alt: self.alt.unwrap_or(0.0),
+        })
+    }
+
+    /// Parse a location from service options, preferring `Coord` options
+    /// and falling back to location metadata
+    pub fn from_options<'a>(options: impl IntoIterator<Item = &'a Options>) -> Option<Self> {
+        let (mut lat, mut lng, mut alt) = (None, None, None);
+
+        for o in options {
+            match o {
+                Options::Coord(c) => return Location::new(c.lat, c.lng, Some(c.alt)).ok(),
+                Options::Metadata(m) => {
+                    let v = match f32::from_str(&m.value) {
+                        Ok(v) => Some(v),
+                        Err(_) => continue,
+                    };
+                    match m.key.as_str() {
+                        "lat" => lat = v,
+                        "lng" => lng = v,
+                        "alt" => alt = v,
+                        _ => (),
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        Location::new(lat?, lng?, alt).ok()
+    }
+
+    /// Fetch the location for a service from its primary page
+    pub fn from_page(page: &DataInfo<Vec<EpDescriptor>>) -> Option<Self> {
+        let private = match &page.private_options {
+            MaybeEncrypted::Cleartext(o) => &o[..],
+            _ => &[],
+        };
+
+        Location::from_options(page.public_options.iter().chain(private.iter()))
+    }
+}
+
+/// Parse a location in the form `LAT,LNG[,ALT]`
+pub fn parse_location(src: &str) -> Result<Location, IotError> {
+    let mut parts = src.split(',').map(|p| f32::from_str(p.trim()));
+
+    let (lat, lng) = match (parts.next(), parts.next()) {
+        (Some(Ok(lat)), Some(Ok(lng))) => (lat, lng),
+        _ => return Err(IotError::InvalidLocation),
+    };
+
+    let alt = match parts.next() {
+        Some(Ok(a)) => Some(a),
+        Some(Err(_)) => return Err(IotError::InvalidLocation),
+        None => None,
+    };
+
+    if parts.next().is_some() {
+        return Err(IotError::InvalidLocation);
+    }
+
+    Location::new(lat, lng, alt)
+}
+
+impl FromStr for Location {
+    type Err = IotError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_location(s)
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:.5},{:.5}", self.lat, self.lng)?;
+        if let Some(alt) = self.alt {
+            write!(f, " ({} m)", alt)?;
+        }
+        Ok(())
+    }
+}
+
+/// Filter services to those located within `radius_km` of `centre`,
+/// ordered by distance. Services without a location are excluded.
+pub fn filter_near<T>(
+    services: Vec<T>,
+    location: impl Fn(&T) -> Option<Location>,
+    centre: &Location,
+    radius_km: f32,
+) -> Vec<(T, f32)> {
+    let mut near: Vec<_> = services
+        .into_iter()
+        .filter_map(|s| {
+            let d = centre.distance_km(&location(&s)?);
+            (d <= radius_km).then(|| (s, d))
+        })
+        .collect();
+
+    near.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(core::cmp::Ordering::Equal));
+    near
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_locations() {
+        assert_eq!(
+            Location::from_str("-36.85, 174.76").unwrap(),
+            Location::new(-36.85, 174.76, None).unwrap()
+        );
+        assert_eq!(
+            Location::from_str("51.5,-0.12,35").unwrap(),
+            Location::new(51.5, -0.12, Some(35.0)).unwrap()
+        );
+
+        for s in ["", "51.5", "91.0,0.0", "0.0,181.0", "1,2,3,4", "a,b"] {
+            assert!(Location::from_str(s).is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn filter_by_distance() {
+        // Auckland to Wellington is ~490 km
+        let akl = Location::new(-36.85, 174.76, None).unwrap();
+        let wlg = Location::new(-41.29, 174.78, None).unwrap();
+
+        let d = akl.distance_km(&wlg);
+        assert!((480.0..500.0).contains(&d), "{d}");
+
+        let services = vec![("wlg", Some(wlg)), ("none", None), ("akl", Some(akl))];
+
+        let near = filter_near(services.clone(), |s| s.1, &akl, 10.0);
+        assert_eq!(near.iter().map(|s| s.0 .0).collect::<Vec<_>>(), vec!["akl"]);
+
+        let near = filter_near(services, |s| s.1, &akl, 1000.0);
+        assert_eq!(
+            near.iter().map(|s| s.0 .0).collect::<Vec<_>>(),
+            vec!["akl", "wlg"]
+        );
+    }
+}
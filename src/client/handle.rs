@@ -0,0 +1,115 @@
+use futures::prelude::*;
+
+use dsf_core::prelude::*;
+use dsf_rpc::{self as rpc, DataInfo, PageBounds, ServiceIdentifier, ServiceInfo};
+
+use super::{IotClient, QueryOptions};
+use crate::error::IotError;
+use crate::prelude::{EpData, EpDescriptor};
+
+/// Number of recent objects fetched to locate the latest data object, as the
+/// most recent objects may be pages (e.g. following descriptor updates)
+const LATEST_WINDOW: usize = 8;
+
+/// High-level handle to an IoT service, combining service information,
+/// endpoint descriptors, and data access
+pub struct IotServiceHandle<'a> {
+    client: &'a mut IotClient,
+    info: ServiceInfo,
+    descriptors: DataInfo<Vec<EpDescriptor>>,
+}
+
+impl<'a> IotServiceHandle<'a> {
+    pub(crate) fn new(
+        client: &'a mut IotClient,
+        info: ServiceInfo,
+        descriptors: DataInfo<Vec<EpDescriptor>>,
+    ) -> Self {
+        Self {
+            client,
+            info,
+            descriptors,
+        }
+    }
+
+    /// Service identifier for use with other client calls
+    pub fn id(&self) -> ServiceIdentifier {
+        ServiceIdentifier::id(self.info.id.clone())
+    }
+
+    /// Fetch service information
+    pub fn info(&self) -> &ServiceInfo {
+        &self.info
+    }
+
+    /// Fetch primary page information
+    pub fn page(&self) -> &DataInfo<Vec<EpDescriptor>> {
+        &self.descriptors
+    }
+
+    /// Fetch endpoint descriptors, if available (decrypted)
+    pub fn descriptors(&self) -> Option<&[EpDescriptor]> {
+        match &self.descriptors.body {
+            MaybeEncrypted::Cleartext(eps) => Some(eps),
+            _ => None,
+        }
+    }
+
+    /// Fetch the latest data object for the service
+    pub async fn latest(&mut self) -> Result<Option<DataInfo<Vec<EpData>>>, IotError> {
+        // Pages are filtered from history, so fetch a window of recent objects
+        let data = self
+            .history(PageBounds {
+                count: Some(LATEST_WINDOW),
+                ..Default::default()
+            })
+            .await?;
+
+        Ok(newest(data, |d| d.index))
+    }
+
+    /// Fetch data objects for the service within the provided bounds
+    pub async fn history(
+        &mut self,
+        bounds: PageBounds,
+    ) -> Result<Vec<DataInfo<Vec<EpData>>>, IotError> {
+        let (_s, _d, data) = self
+            .client
+            .query(QueryOptions {
+                service: self.id(),
+                bounds,
+            })
+            .await?;
+
+        Ok(data)
+    }
+
+    /// Subscribe to data from the service
+    pub async fn subscribe(&mut self) -> Result<impl Stream<Item = ()>, IotError> {
+        let s = self
+            .client
+            .subscribe(rpc::SubscribeOptions { service: self.id() })
+            .await?;
+
+        Ok(s)
+    }
+}
+
+/// Select the newest object by index
+fn newest<T, K: Ord>(objects: Vec<T>, index: impl Fn(&T) -> K) -> Option<T> {
+    objects.into_iter().max_by_key(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latest_data_object() {
+        // Objects 7 and 6 are pages, filtered from the fetched window
+        let window = vec![(5, "c"), (4, "b"), (3, "a")];
+        assert_eq!(newest(window, |(i, _)| *i), Some((5, "c")));
+
+        assert_eq!(newest(Vec::<(u32, &str)>::new(), |(i, _)| *i), None);
+    }
+}
@@ -0,0 +1,409 @@
+//! Home Assistant MQTT discovery mapping, translating IoT service classes and
+//! endpoints to discovery payloads and Home Assistant command payloads back
+//! to endpoint control values.
+//!
+//! Endpoint states are published to `<base>/<service>/<index>/state` and
+//! commands received on `<base>/<service>/<index>/set`, with discovery
+//! configurations published to `homeassistant/<component>/<service>/<object>/config`.
+//! Services of the [`DeviceClass::SmartLight`] class are mapped to a single
+//! `light` entity, other endpoints to one entity each.
+
+use serde_json::{json, Map, Value};
+
+use crate::endpoint::{DeviceClass, EpDescriptor, EpFlags, EpKind, EpRole, EpValue};
+use crate::error::IotError;
+
+/// Default Home Assistant discovery topic prefix
+pub const DISCOVERY_PREFIX: &str = "homeassistant";
+
+/// Default base topic for endpoint state and command topics
+pub const DEFAULT_BASE_TOPIC: &str = "dsf-iot";
+
+/// Home Assistant entity components
+#[derive(Debug, Copy, Clone, PartialEq, strum::IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum Component {
+    Sensor,
+    BinarySensor,
+    Switch,
+    Number,
+    Light,
+}
+
+impl Component {
+    /// Select the component for an endpoint, returning `None` for endpoints
+    /// with no Home Assistant equivalent
+    pub fn for_endpoint(d: &EpDescriptor) -> Option<Self> {
+        let writable = d.flags.contains(EpFlags::W);
+
+        match (d.kind, writable) {
+            (EpKind::Colour, _) => None,
+            (EpKind::State, true) => Some(Self::Switch),
+            (EpKind::State, false) => Some(Self::BinarySensor),
+            (EpKind::Event, _) => Some(Self::Sensor),
+            (_, true) => Some(Self::Number),
+            (_, false) => Some(Self::Sensor),
+        }
+    }
+}
+
+impl core::fmt::Display for Component {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.pad(<&'static str>::from(self))
+    }
+}
+
+/// Device information for discovery payloads
+#[derive(Debug, Clone, PartialEq)]
+pub struct HassDevice {
+    /// Service ID, used for unique entity IDs and topics
+    pub id: String,
+    /// Service name, defaults to the service ID
+    pub name: Option<String>,
+    /// Service device class, if published
+    pub class: Option<DeviceClass>,
+}
+
+/// Home Assistant discovery configuration for an entity
+#[derive(Debug, Clone, PartialEq)]
+pub struct Discovery {
+    pub component: Component,
+    /// Node (service) ID
+    pub node_id: String,
+    /// Entity object ID within the node
+    pub object_id: String,
+    pub config: Value,
+}
+
+impl Discovery {
+    /// Discovery configuration topic under the provided prefix
+    pub fn topic(&self, prefix: &str) -> String {
+        format!(
+            "{}/{}/{}/{}/config",
+            prefix, self.component, self.node_id, self.object_id
+        )
+    }
+}
+
+/// State topic for an endpoint
+pub fn state_topic(base: &str, id: &str, index: usize) -> String {
+    format!("{base}/{id}/{index}/state")
+}
+
+/// Command topic for an endpoint
+pub fn command_topic(base: &str, id: &str, index: usize) -> String {
+    format!("{base}/{id}/{index}/set")
+}
+
+/// Parse a command topic, returning the service ID and endpoint index
+pub fn parse_command_topic<'a>(base: &str, topic: &'a str) -> Option<(&'a str, usize)> {
+    let rest = topic.strip_prefix(base)?.strip_prefix('/')?;
+    let rest = rest.strip_suffix("/set")?;
+
+    let (id, index) = rest.rsplit_once('/')?;
+    if id.is_empty() || id.contains('/') {
+        return None;
+    }
+
+    Some((id, index.parse().ok()?))
+}
+
+/// Home Assistant device class for an endpoint kind
+fn device_class(kind: EpKind, class: Option<DeviceClass>) -> Option<&'static str> {
+    let c = match kind {
+        EpKind::Temperature => "temperature",
+        EpKind::Humidity => "humidity",
+        EpKind::Pressure => "pressure",
+        EpKind::Co2 => "carbon_dioxide",
+        EpKind::Battery => "battery",
+        EpKind::Rssi => "signal_strength",
+        EpKind::Uptime => "duration",
+        EpKind::Power => "power",
+        EpKind::Energy => "energy",
+        EpKind::State if class == Some(DeviceClass::DoorSensor) => "door",
+        EpKind::State if class == Some(DeviceClass::SmartPlug) => "outlet",
+        _ => return None,
+    };
+    Some(c)
+}
+
+/// Home Assistant unit of measurement for an endpoint kind
+fn unit(kind: EpKind) -> Option<String> {
+    match kind {
+        EpKind::State | EpKind::Colour | EpKind::ErrorCount | EpKind::Event => None,
+        // Home Assistant expects plain percentages for humidity
+        EpKind::Humidity => Some("%".to_string()),
+        _ => Some(kind.unit()),
+    }
+}
+
+/// Build Home Assistant discovery configurations for a service
+pub fn discovery(device: &HassDevice, descriptors: &[EpDescriptor], base: &str) -> Vec<Discovery> {
+    let name = device.name.as_deref().unwrap_or(&device.id);
+
+    let mut dev = json!({
+        "identifiers": [format!("dsf-iot-{}", device.id)],
+        "name": name,
+        "manufacturer": "dsf-iot",
+    });
+    if let Some(c) = device.class {
+        dev["model"] = json!(c.to_string());
+    }
+
+    let mut entities = vec![];
+    let mut skip = 0;
+
+    // Lights combine state and brightness endpoints in a single entity
+    if let Some(DeviceClass::SmartLight) = device.class {
+        if DeviceClass::SmartLight.matches(descriptors) {
+            entities.push(Discovery {
+                component: Component::Light,
+                node_id: device.id.clone(),
+                object_id: "light".to_string(),
+                config: json!({
+                    "name": Value::Null,
+                    "unique_id": format!("{}_light", device.id),
+                    "state_topic": state_topic(base, &device.id, 0),
+                    "command_topic": command_topic(base, &device.id, 0),
+                    "brightness_state_topic": state_topic(base, &device.id, 1),
+                    "brightness_command_topic": command_topic(base, &device.id, 1),
+                    "brightness_scale": 100,
+                    "device": dev.clone(),
+                }),
+            });
+            skip = DeviceClass::SmartLight.descriptors().len();
+        }
+    }
+
+    for (i, d) in descriptors.iter().enumerate().skip(skip) {
+        let component = match Component::for_endpoint(d) {
+            Some(c) => c,
+            None => continue,
+        };
+
+        let mut c = Map::new();
+        c.insert("name".into(), json!(d.kind.to_string()));
+        c.insert("unique_id".into(), json!(format!("{}_{}", device.id, i)));
+        c.insert(
+            "state_topic".into(),
+            json!(state_topic(base, &device.id, i)),
+        );
+        c.insert("device".into(), dev.clone());
+
+        if let Some(v) = device_class(d.kind, device.class) {
+            c.insert("device_class".into(), json!(v));
+        }
+        if component != Component::BinarySensor && component != Component::Switch {
+            if let Some(v) = unit(d.kind) {
+                c.insert("unit_of_measurement".into(), json!(v));
+            }
+        }
+
+        match component {
+            Component::Sensor => {
+                let state_class = match d.kind {
+                    EpKind::Energy | EpKind::ErrorCount | EpKind::Event => "total_increasing",
+                    _ => "measurement",
+                };
+                c.insert("state_class".into(), json!(state_class));
+            }
+            Component::Switch | Component::Number | Component::Light => {
+                c.insert(
+                    "command_topic".into(),
+                    json!(command_topic(base, &device.id, i)),
+                );
+            }
+            Component::BinarySensor => (),
+        }
+
+        if component == Component::Number {
+            for (k, v) in [
+                ("min", d.constraints.min),
+                ("max", d.constraints.max),
+                ("step", d.constraints.step),
+            ] {
+                if let Some(v) = v {
+                    c.insert(k.into(), json!(v));
+                }
+            }
+            if d.role() == EpRole::Config {
+                c.insert("entity_category".into(), json!("config"));
+            }
+        }
+
+        entities.push(Discovery {
+            component,
+            node_id: device.id.clone(),
+            object_id: i.to_string(),
+            config: Value::Object(c),
+        });
+    }
+
+    entities
+}
+
+/// Render an endpoint value as a Home Assistant state payload
+pub fn state_payload(value: &EpValue) -> String {
+    match value {
+        EpValue::Bool(true) => "ON".to_string(),
+        EpValue::Bool(false) => "OFF".to_string(),
+        EpValue::Text(v) => v.to_string(),
+        EpValue::Int32(v) => v.to_string(),
+        EpValue::Counter64(v) => v.to_string(),
+        EpValue::Event { count, .. } => count.to_string(),
+        // Durations are reported in seconds
+        EpValue::DurationMs(v) => format!("{:.3}", *v as f32 / 1000.0),
+        v => match v.as_f32() {
+            Some(f) => f.to_string(),
+            None => v.to_string(),
+        },
+    }
+}
+
+/// Parse a Home Assistant command payload into a control value for an
+/// endpoint, validated against the endpoint descriptor
+pub fn command_value(d: &EpDescriptor, payload: &str) -> Result<EpValue, IotError> {
+    let payload = payload.trim();
+
+    let v = match d.kind {
+        EpKind::State => match payload {
+            "ON" => EpValue::Bool(true),
+            "OFF" => EpValue::Bool(false),
+            _ => return Err(IotError::MismatchedType),
+        },
+        EpKind::Colour => return Err(IotError::MismatchedType),
+        kind => {
+            let v: f32 = payload.parse().map_err(|_| IotError::MismatchedType)?;
+            match kind {
+                EpKind::Brightness | EpKind::Battery => EpValue::Percent(v),
+                _ => EpValue::Float32(v),
+            }
+        }
+    };
+
+    d.validate(&v)?;
+
+    Ok(v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoint::EpConstraints;
+
+    fn device(class: Option<DeviceClass>) -> HassDevice {
+        HassDevice {
+            id: "ABCD".to_string(),
+            name: Some("Kitchen".to_string()),
+            class,
+        }
+    }
+
+    #[test]
+    fn light_discovery() {
+        let dev = device(Some(DeviceClass::SmartLight));
+        let mut descriptors = DeviceClass::SmartLight.descriptors().to_vec();
+        descriptors.push(EpDescriptor::new(EpKind::Temperature, EpFlags::R));
+
+        let d = discovery(&dev, &descriptors, DEFAULT_BASE_TOPIC);
+        assert_eq!(d.len(), 2);
+
+        assert_eq!(d[0].component, Component::Light);
+        assert_eq!(
+            d[0].topic(DISCOVERY_PREFIX),
+            "homeassistant/light/ABCD/light/config"
+        );
+        assert_eq!(d[0].config["command_topic"], "dsf-iot/ABCD/0/set");
+        assert_eq!(
+            d[0].config["brightness_command_topic"],
+            "dsf-iot/ABCD/1/set"
+        );
+        assert_eq!(d[0].config["device"]["model"], "smart-light");
+
+        // Additional endpoints follow the class entity
+        assert_eq!(d[1].component, Component::Sensor);
+        assert_eq!(d[1].object_id, "3");
+        assert_eq!(d[1].config["device_class"], "temperature");
+        assert_eq!(d[1].config["unit_of_measurement"], "°C");
+        assert_eq!(d[1].config["state_topic"], "dsf-iot/ABCD/3/state");
+    }
+
+    #[test]
+    fn endpoint_discovery() {
+        let dev = device(Some(DeviceClass::DoorSensor));
+        let mut descriptors = DeviceClass::DoorSensor.descriptors().to_vec();
+        descriptors.push(
+            EpDescriptor::new(EpKind::Brightness, EpFlags::RW).with_constraints(EpConstraints {
+                min: Some(0.0),
+                max: Some(50.0),
+                step: None,
+            }),
+        );
+
+        let d = discovery(&dev, &descriptors, "iot");
+        let c: Vec<_> = d.iter().map(|d| d.component).collect();
+        assert_eq!(
+            c,
+            vec![
+                Component::BinarySensor,
+                Component::Sensor,
+                Component::Sensor,
+                Component::Number
+            ]
+        );
+
+        assert_eq!(d[0].config["device_class"], "door");
+        assert!(d[0].config.get("command_topic").is_none());
+        assert_eq!(d[1].config["state_class"], "total_increasing");
+        assert_eq!(d[3].config["command_topic"], "iot/ABCD/3/set");
+        assert_eq!(d[3].config["max"], 50.0);
+        assert!(d[3].config.get("step").is_none());
+    }
+
+    #[test]
+    fn command_mapping() {
+        let state = EpDescriptor::new(EpKind::State, EpFlags::RW);
+        let brightness =
+            EpDescriptor::new(EpKind::Brightness, EpFlags::RW).with_constraints(EpConstraints {
+                min: Some(0.0),
+                max: Some(100.0),
+                step: None,
+            });
+
+        assert_eq!(
+            parse_command_topic("dsf-iot", "dsf-iot/ABCD/1/set"),
+            Some(("ABCD", 1))
+        );
+        assert_eq!(parse_command_topic("dsf-iot", "dsf-iot/ABCD/1/state"), None);
+        assert_eq!(parse_command_topic("dsf-iot", "other/ABCD/1/set"), None);
+
+        assert_eq!(command_value(&state, "ON").unwrap(), EpValue::Bool(true));
+        assert_eq!(command_value(&state, "OFF").unwrap(), EpValue::Bool(false));
+        assert!(command_value(&state, "toggle").is_err());
+
+        assert_eq!(
+            command_value(&brightness, "42").unwrap(),
+            EpValue::Percent(42.0)
+        );
+        assert!(command_value(&brightness, "120").is_err());
+
+        // Read-only endpoints reject commands
+        let temp = EpDescriptor::new(EpKind::Temperature, EpFlags::R);
+        assert!(matches!(
+            command_value(&temp, "20"),
+            Err(IotError::ReadOnly)
+        ));
+
+        // State payloads round-trip
+        assert_eq!(state_payload(&EpValue::Bool(true)), "ON");
+        assert_eq!(state_payload(&EpValue::Percent(42.0)), "42");
+        assert_eq!(
+            state_payload(&EpValue::Event {
+                count: 3,
+                time_ms: 1_000
+            }),
+            "3"
+        );
+        assert_eq!(state_payload(&EpValue::DurationMs(1500)), "1.500");
+    }
+}
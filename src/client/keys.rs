@@ -0,0 +1,207 @@
+//! Service identity (key) bundles, with passphrase encryption for secure
+//! backup and transfer of device identities.
+//!
+//! Encrypted bundles contain a header (magic, version, argon2 parameters, salt
+//! and nonce) followed by the ChaCha20-Poly1305 encrypted JSON identity.
+
+use core::str::FromStr;
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use dsf_core::keys::Keys;
+use dsf_core::prelude::*;
+
+use crate::error::IotError;
+
+/// Encrypted bundle magic
+const MAGIC: &[u8; 4] = b"DSFK";
+/// Encrypted bundle format version
+const VERSION: u8 = 1;
+
+/// Argon2 parameters (memory cost, time cost, parallelism) as u32 LE
+const PARAMS_LEN: usize = 12;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = MAGIC.len() + 1 + PARAMS_LEN + SALT_LEN + NONCE_LEN;
+
+/// Upper bounds on argon2 parameters accepted from bundles, so malformed
+/// or malicious files cannot force excessive key derivation costs
+const MAX_M_COST: u32 = 1 << 20;
+const MAX_T_COST: u32 = 16;
+const MAX_P_COST: u32 = 16;
+
+/// Serialised service identity
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Identity {
+    pub id: String,
+    pub pub_key: Option<String>,
+    pub pri_key: Option<String>,
+    pub sec_key: Option<String>,
+}
+
+impl Identity {
+    /// Create an identity from a service ID and keys
+    pub fn new(id: &Id, keys: &Keys) -> Self {
+        Self {
+            id: id.to_string(),
+            pub_key: keys.pub_key.as_ref().map(|k| k.to_string()),
+            pri_key: keys.pri_key.as_ref().map(|k| k.to_string()),
+            sec_key: keys.sec_key.as_ref().map(|k| k.to_string()),
+        }
+    }
+
+    /// Parse the service ID and keys for this identity
+    pub fn keys(&self) -> Result<(Id, Keys), IotError> {
+        let id = Id::from_str(&self.id).map_err(|_| IotError::InvalidKeyFile)?;
+
+        let keys = Keys {
+            pub_key: parse_key(&self.pub_key)?,
+            pri_key: parse_key(&self.pri_key)?,
+            sec_key: parse_key(&self.sec_key)?,
+            sym_keys: None,
+        };
+
+        Ok((id, keys))
+    }
+
+    /// Encrypt this identity using the provided passphrase
+    pub fn encrypt(&self, passphrase: &str) -> Result<Vec<u8>, IotError> {
+        let body = Zeroizing::new(serde_json::to_vec(self)?);
+
+        let params = Params::default();
+
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(MAGIC);
+        header.push(VERSION);
+        for p in [params.m_cost(), params.t_cost(), params.p_cost()] {
+            header.extend_from_slice(&p.to_le_bytes());
+        }
+
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        header.extend_from_slice(&salt);
+
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        header.extend_from_slice(&nonce);
+
+        let cipher = cipher(passphrase, &salt, params)?;
+        let encrypted = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: body.as_slice(),
+                    aad: &header,
+                },
+            )
+            .map_err(|_| IotError::KeyDecrypt)?;
+
+        Ok([header, encrypted].concat())
+    }
+
+    /// Decrypt an identity using the provided passphrase
+    pub fn decrypt(data: &[u8], passphrase: &str) -> Result<Self, IotError> {
+        if data.len() < HEADER_LEN || &data[..MAGIC.len()] != MAGIC {
+            return Err(IotError::InvalidKeyFile);
+        }
+        if data[MAGIC.len()] != VERSION {
+            return Err(IotError::InvalidKeyFile);
+        }
+
+        let (header, encrypted) = data.split_at(HEADER_LEN);
+        let params = parse_params(&header[MAGIC.len() + 1..][..PARAMS_LEN])?;
+        let salt = &header[MAGIC.len() + 1 + PARAMS_LEN..][..SALT_LEN];
+        let nonce = Nonce::from_slice(&header[HEADER_LEN - NONCE_LEN..]);
+
+        let cipher = cipher(passphrase, salt, params)?;
+        let body = cipher
+            .decrypt(
+                nonce,
+                Payload {
+                    msg: encrypted,
+                    aad: header,
+                },
+            )
+            .map(Zeroizing::new)
+            .map_err(|_| IotError::KeyDecrypt)?;
+
+        let identity = serde_json::from_slice(&body)?;
+        Ok(identity)
+    }
+}
+
+/// Parse an optional key string
+fn parse_key<K: FromStr>(k: &Option<String>) -> Result<Option<K>, IotError> {
+    match k {
+        Some(k) => K::from_str(k)
+            .map(Some)
+            .map_err(|_| IotError::InvalidKeyFile),
+        None => Ok(None),
+    }
+}
+
+/// Parse and bound-check argon2 parameters from a bundle header
+fn parse_params(data: &[u8]) -> Result<Params, IotError> {
+    let mut p = data
+        .chunks_exact(4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+    let (m, t, par) = match (p.next(), p.next(), p.next()) {
+        (Some(m), Some(t), Some(par)) => (m, t, par),
+        _ => return Err(IotError::InvalidKeyFile),
+    };
+
+    if m > MAX_M_COST || t > MAX_T_COST || par > MAX_P_COST {
+        return Err(IotError::InvalidKeyFile);
+    }
+
+    Params::new(m, t, par, None).map_err(|_| IotError::InvalidKeyFile)
+}
+
+/// Derive a bundle cipher from a passphrase and salt using argon2
+fn cipher(passphrase: &str, salt: &[u8], params: Params) -> Result<ChaCha20Poly1305, IotError> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key[..])
+        .map_err(|_| IotError::KeyDecrypt)?;
+
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key[..])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity_encryption() {
+        let i = Identity {
+            id: "test-id".to_string(),
+            pub_key: Some("test-pub-key".to_string()),
+            pri_key: Some("test-pri-key".to_string()),
+            sec_key: None,
+        };
+
+        let data = i.encrypt("passphrase").unwrap();
+        assert_eq!(&data[..4], MAGIC);
+        assert_eq!(Identity::decrypt(&data, "passphrase").unwrap(), i);
+
+        assert!(matches!(
+            Identity::decrypt(&data, "incorrect"),
+            Err(IotError::KeyDecrypt)
+        ));
+        assert!(matches!(
+            Identity::decrypt(&data[..8], "passphrase"),
+            Err(IotError::InvalidKeyFile)
+        ));
+
+        // Excessive argon2 costs are rejected before key derivation
+        let mut costly = data.clone();
+        costly[5..9].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            Identity::decrypt(&costly, "passphrase"),
+            Err(IotError::InvalidKeyFile)
+        ));
+    }
+}
@@ -0,0 +1,269 @@
+//! Object layout previews, encoding service pages and data objects locally
+//! to show option kinds and sizes without contacting the daemon.
+//!
+//! Body entries and lengths are taken from the encoded body. Object framing
+//! (header, ID, signature, encryption) and metadata sizes are approximations
+//! of the `dsf-core` encoding rather than derived from it, so the total object
+//! length is an estimate only.
+
+use core::convert::TryInto;
+
+use byteorder::{ByteOrder, LittleEndian};
+use encdec::Encode;
+
+use crate::endpoint::iot_option_kinds::*;
+use crate::error::IotError;
+
+use super::{CreateOptions, PublishOptions};
+
+/// Default engine object buffer size (see [`crate::IotEngine`])
+pub const DEFAULT_BUFFER_LEN: usize = 512;
+
+/// Approximate object header length
+const HEADER_LEN: usize = 16;
+/// Approximate service ID length
+const ID_LEN: usize = 32;
+/// Approximate object signature length
+const SIGNATURE_LEN: usize = 64;
+/// Approximate encryption overhead (nonce and tag) for encrypted sections
+const ENCRYPTION_LEN: usize = 40;
+/// Option header length (kind and length)
+const OPTION_HEADER_LEN: usize = 4;
+
+/// Encoded option entry within an object body
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutEntry {
+    /// Offset of the option within the body
+    pub offset: usize,
+    /// Option kind
+    pub kind: u16,
+    /// Encoded option length (including header)
+    pub len: usize,
+    /// Entry description
+    pub name: String,
+}
+
+/// Layout of an encoded IoT object
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectLayout {
+    /// Object kind (page or data)
+    pub kind: &'static str,
+    /// Body option entries
+    pub entries: Vec<LayoutEntry>,
+    /// Encoded body length
+    pub body_len: usize,
+    /// Encoded metadata option length
+    pub meta_len: usize,
+    /// Whether the body and private options are encrypted
+    pub encrypted: bool,
+}
+
+impl ObjectLayout {
+    /// Encode and validate a service page for the provided create options
+    pub fn page(opts: &CreateOptions) -> Result<Self, IotError> {
+        // Check endpoints fit the target engine info object
+        let endpoints = opts.descriptors();
+        if endpoints.len() > opts.max_endpoints {
+            return Err(IotError::TooManyEndpoints {
+                count: endpoints.len(),
+                max: opts.max_endpoints,
+            });
+        }
+
+        let names = endpoints
+            .iter()
+            .map(|e| format!("{} ({:?})", e.kind, e.flags));
+        let rpc: dsf_rpc::CreateOptions = opts.clone().try_into()?;
+
+        Ok(Self {
+            kind: "page",
+            entries: entries(rpc.body.as_deref().unwrap_or(&[]), names)?,
+            body_len: rpc.body.map(|b| b.len()).unwrap_or(0),
+            meta_len: meta_len(&opts.meta),
+            encrypted: !opts.public,
+        })
+    }
+
+    /// Encode and validate a data object for the provided publish options,
+    /// `encrypted` should match the target service
+    pub fn data(opts: &PublishOptions, encrypted: bool) -> Result<Self, IotError> {
+        // Check values fit the target engine data object
+        if opts.data.len() > opts.max_endpoints {
+            return Err(IotError::TooManyEndpoints {
+                count: opts.data.len(),
+                max: opts.max_endpoints,
+            });
+        }
+
+        let mut body = vec![0u8; opts.data.encode_len()?];
+        let n = opts.data.encode(&mut body)?;
+
+        let names = opts.data.iter().map(|d| format!("{}", d.value));
+
+        Ok(Self {
+            kind: "data",
+            entries: entries(&body[..n], names)?,
+            body_len: n,
+            meta_len: meta_len(&opts.meta),
+            encrypted,
+        })
+    }
+
+    /// Estimated encoded object length, including approximate header, ID,
+    /// signature, and encryption overheads
+    pub fn estimated_len(&self) -> usize {
+        let mut n = HEADER_LEN + ID_LEN + self.body_len + self.meta_len + SIGNATURE_LEN;
+
+        // Body and private options are encrypted separately
+        if self.encrypted {
+            n += ENCRYPTION_LEN * 2;
+        }
+
+        n
+    }
+}
+
+impl core::fmt::Display for ObjectLayout {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let enc = match self.encrypted {
+            true => "encrypted",
+            false => "public",
+        };
+        writeln!(f, "Object: {} ({})", self.kind, enc)?;
+
+        writeln!(f, "  body:")?;
+        for e in &self.entries {
+            writeln!(
+                f,
+                "    - {:4} {:16} {:4} bytes  {}",
+                e.offset,
+                option_name(e.kind),
+                e.len,
+                e.name
+            )?;
+        }
+
+        writeln!(f, "  body length: {} bytes", self.body_len)?;
+        writeln!(f, "  metadata length (estimated): {} bytes", self.meta_len)?;
+
+        let n = self.estimated_len();
+        write!(
+            f,
+            "  estimated object length: ~{} bytes ({} byte engine buffer: {})",
+            n,
+            DEFAULT_BUFFER_LEN,
+            if n <= DEFAULT_BUFFER_LEN {
+                "likely fits"
+            } else {
+                "likely OVERRUN"
+            }
+        )
+    }
+}
+
+/// Split an encoded body into option entries
+fn entries(
+    body: &[u8],
+    mut names: impl Iterator<Item = String>,
+) -> Result<Vec<LayoutEntry>, IotError> {
+    let mut entries = vec![];
+    let mut offset = 0;
+
+    while offset < body.len() {
+        if body.len() - offset < OPTION_HEADER_LEN {
+            return Err(IotError::Overrun);
+        }
+
+        let kind = LittleEndian::read_u16(&body[offset..]);
+        let len = OPTION_HEADER_LEN + LittleEndian::read_u16(&body[offset + 2..]) as usize;
+
+        entries.push(LayoutEntry {
+            offset,
+            kind,
+            len,
+            name: names.next().unwrap_or_default(),
+        });
+
+        offset += len;
+    }
+
+    Ok(entries)
+}
+
+/// Estimate metadata length, encoded as key:value options
+fn meta_len(meta: &[(String, String)]) -> usize {
+    meta.iter()
+        .map(|(k, v)| OPTION_HEADER_LEN + k.len() + 1 + v.len())
+        .sum()
+}
+
+/// Fetch a name for an IoT option kind
+pub fn option_name(kind: u16) -> &'static str {
+    match kind {
+        ENDPOINT_DESCRIPTOR => "descriptor",
+        VALUE_BOOL_FALSE | VALUE_BOOL_TRUE => "bool",
+        VALUE_FLOAT => "float",
+        VALUE_INT => "int",
+        VALUE_STRING => "string",
+        VALUE_RAW => "raw",
+        VALUE_COUNTER => "counter",
+        VALUE_DURATION => "duration",
+        VALUE_PERCENT => "percent",
+        DATA_QUALITY => "quality",
+        _ => "unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoint::{EpData, EpDescriptor, EpFlags, EpKind, MAX_ENDPOINTS};
+    use dsf_rpc::ServiceIdentifier;
+
+    #[test]
+    fn page_layout() {
+        let opts = CreateOptions {
+            endpoints: vec![
+                EpDescriptor::new(EpKind::Temperature, EpFlags::R),
+                EpDescriptor::new(EpKind::State, EpFlags::RW),
+            ],
+            public: true,
+            ..Default::default()
+        };
+
+        let l = ObjectLayout::page(&opts).unwrap();
+        assert_eq!(l.body_len, 16);
+        assert_eq!(l.entries.len(), 2);
+        assert_eq!(l.entries[1].offset, 8);
+        assert_eq!(l.entries[1].kind, ENDPOINT_DESCRIPTOR);
+        assert!(!l.encrypted);
+    }
+
+    #[test]
+    fn data_layout() {
+        let opts = PublishOptions {
+            service: ServiceIdentifier {
+                id: None,
+                index: Some(0),
+            },
+            data: vec![EpData::new(21.5.into()), EpData::new(true.into())],
+            meta: vec![],
+            max_endpoints: MAX_ENDPOINTS,
+        };
+
+        let l = ObjectLayout::data(&opts, true).unwrap();
+        assert_eq!(l.body_len, 12);
+        assert_eq!(l.entries[0].kind, VALUE_FLOAT);
+        assert_eq!(l.entries[1].kind, VALUE_BOOL_TRUE);
+        assert_eq!(l.entries[1].len, 4);
+
+        let opts = PublishOptions {
+            max_endpoints: 1,
+            ..opts
+        };
+        assert!(matches!(
+            ObjectLayout::data(&opts, true),
+            Err(IotError::TooManyEndpoints { count: 2, max: 1 })
+        ));
+    }
+}
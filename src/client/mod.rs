@@ -1,6 +1,7 @@
 use core::convert::TryInto;
+use std::collections::{HashMap, VecDeque};
 
-use futures::prelude::*;
+use futures::{prelude::*, stream};
 use log::{debug, error, warn};
 
 use encdec::EncodeExt;
@@ -21,6 +22,7 @@ use dsf_core::{
 };
 use rpc::{NsRegisterInfo, NsSearchInfo, PageBounds};
 
+use crate::endpoint::{apply_delta, is_delta};
 use crate::error::IotError;
 use crate::prelude::{EpData, EpDescriptor, EpFlags};
 use crate::IoT;
@@ -28,18 +30,143 @@ use crate::IoT;
 pub mod options;
 pub use options::*;
 
+pub mod handle;
+pub use handle::IotServiceHandle;
+
+pub mod cache;
+pub use cache::ClientCache;
+
+pub mod prometheus;
+
+pub mod layout;
+pub use layout::ObjectLayout;
+
+pub mod audit;
+pub use audit::{AuditItem, AuditReport};
+
+pub mod composite;
+pub use composite::{parse_composite_source, Composite, CompositeSource};
+
+pub mod watch;
+pub use watch::ValueFilter;
+
+pub mod reconnect;
+pub use reconnect::{RetryConfig, SubscriptionEvent};
+
+pub mod fetch;
+pub use fetch::{FetchFailure, FetchProgress, FetchReport, FetchTarget, DEFAULT_CONCURRENCY};
+
+pub mod keys;
+pub use keys::Identity;
+
+pub mod geo;
+pub use geo::{parse_location, Location};
+
+pub mod access;
+pub use access::AccessSummary;
+
+pub mod provision;
+pub use provision::{parse_manifest, ManifestEntry, ProvisionReport, Provisioned};
+
+pub mod stats;
+pub use stats::EpStats;
+
+pub mod events;
+pub use events::EventTracker;
+
+pub mod profile;
+pub use profile::{load_profiles, Profile, Profiles};
+
+pub mod homeassistant;
+pub use homeassistant::HassDevice;
+
 /// IotClient wraps a `dsf_client::Client` and provides interfaces to interact with DSF-IoT services
 /// TODO: one day this could be an extension trait?
 pub struct IotClient {
     client: Client,
+    config: Config,
+    retry: RetryConfig,
+    concurrency: usize,
+    progress: Option<FetchProgress>,
+    cache: Option<ClientCache>,
+    pages: PageCache,
+}
+
+/// Decoded primary pages, keyed by service ID. Entries are used while the
+/// service primary page signature matches, and replaced when a service
+/// publishes a new primary page.
+pub(crate) type PageCache = HashMap<Id, DataInfo<Vec<EpDescriptor>>>;
+
+/// Fetch the cached page for a service if it matches the current primary page
+pub(crate) fn cached_page<'a>(
+    pages: &'a PageCache,
+    info: &ServiceInfo,
+) -> Option<&'a DataInfo<Vec<EpDescriptor>>> {
+    match (pages.get(&info.id), &info.primary_page) {
+        (Some(p), Some(sig)) if &p.signature == sig => Some(p),
+        _ => None,
+    }
 }
 
 impl IotClient {
     /// Create a new DSF-IoT client using the provided path
     pub async fn new<C: Into<Config>>(config: C) -> Result<Self, IotError> {
-        let client = Client::new(config).await?;
+        let config = config.into();
+        let client = Client::new(config.clone()).await?;
+
+        Ok(Self {
+            client,
+            config,
+            retry: RetryConfig::default(),
+            concurrency: DEFAULT_CONCURRENCY,
+            progress: None,
+            cache: None,
+            pages: PageCache::new(),
+        })
+    }
+
+    /// Set the maximum number of concurrent daemon connections for multi-service fetches
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
 
-        Ok(Self { client })
+    /// Report progress of multi-service fetches via the provided callback
+    pub fn with_progress(mut self, progress: FetchProgress) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Set retry / backoff configuration for reconnecting subscriptions
+    pub fn with_retry(mut self, retry: RetryConfig) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Record retrieved service information and data in the provided cache
+    pub fn with_cache(mut self, cache: ClientCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Update the cache (if enabled) with service information and data
+    fn update_cache(
+        &mut self,
+        info: &ServiceInfo,
+        page: &DataInfo<Vec<EpDescriptor>>,
+        data: &[DataInfo<Vec<EpData>>],
+    ) {
+        let cache = match &mut self.cache {
+            Some(c) => c,
+            None => return,
+        };
+
+        cache.update_service(info, page);
+        cache.update_data(&info.id, data);
+
+        if let Err(e) = cache.save_periodic() {
+            warn!("Failed to write cache: {:?}", e);
+        }
     }
 
     /// Access base client object
@@ -49,7 +176,7 @@ impl IotClient {
 
     /// Create a new IoT service
     pub async fn create(&mut self, options: CreateOptions) -> Result<ServiceHandle, IotError> {
-        debug!("Creating service: {:?}", options.endpoints);
+        debug!("Creating service: {:?}", options.descriptors());
 
         let encoded = options.try_into()?;
 
@@ -62,42 +189,140 @@ impl IotClient {
         Ok(r)
     }
 
+    /// Create services for each manifest entry, collecting device identities
+    /// for key bundles and per-device failures
+    pub async fn provision(
+        &mut self,
+        entries: &[ManifestEntry],
+        public: bool,
+        mut on_created: impl FnMut(&Provisioned) -> Result<(), IotError>,
+    ) -> ProvisionReport {
+        let mut report = ProvisionReport::default();
+
+        for e in entries {
+            // Handle each device as it is created (e.g. saving keys), so later
+            // failures do not lose identities for created devices
+            match self
+                .provision_one(e, public)
+                .await
+                .and_then(|p| on_created(&p).map(|_| p))
+            {
+                Ok(p) => report.created.push(p),
+                Err(err) => {
+                    warn!("Failed to provision {}: {:?}", e.name, err);
+                    report.failures.push((e.name.clone(), err));
+                }
+            }
+        }
+
+        report
+    }
+
+    /// Create a service for a manifest entry and fetch its identity
+    async fn provision_one(
+        &mut self,
+        entry: &ManifestEntry,
+        public: bool,
+    ) -> Result<Provisioned, IotError> {
+        let h = self.create(entry.create_options(public)).await?;
+
+        let (_h, info) = self
+            .client
+            .info(InfoOptions {
+                service: ServiceIdentifier::id(h.id.clone()),
+            })
+            .await?;
+
+        let keys = Keys {
+            pub_key: Some(info.public_key.clone()),
+            pri_key: info.private_key.clone(),
+            sec_key: info.secret_key.clone(),
+            sym_keys: None,
+        };
+
+        Ok(Provisioned {
+            name: entry.name.clone(),
+            id: info.id.clone(),
+            identity: Identity::new(&info.id, &keys),
+        })
+    }
+
     /// Discover local IoT services
     pub async fn discover(
         &mut self,
         opts: DiscoverOptions,
     ) -> Result<Vec<(ServiceInfo, DataInfo<Vec<EpDescriptor>>)>, IotError> {
-        // Build discovery filters
-        let eps: Vec<_> = opts
-            .endpoints
-            .iter()
-            .map(|k| EpDescriptor::new(*k, EpFlags::empty()))
-            .collect();
-        let (body, _) = eps.encode_vec()?;
-
         // Issue discovery request
-        let locate_info = self
-            .client
-            .discover(rpc::DiscoverOptions {
-                application_id: 1,
-                body: Some(body),
-                filters: opts.options.to_vec(),
-            })
-            .await?;
+        let locate_info = self.client.discover(discover_request(&opts)?).await?;
 
-        // Load information for discovered services
-        let mut services = vec![];
-        for i in &locate_info {
-            let (s, d) = self
-                .info(InfoOptions {
-                    service: ServiceIdentifier::id(i.id.clone()),
-                })
-                .await?;
+        // Responses arrive via multiple paths, fetch each service once
+        // (in order of first response, so lower latency services are ranked first)
+        let ids = dedup_first(locate_info.iter().map(|i| i.id.clone()), |id| id.clone());
 
-            services.push((s, d));
+        // Load information for discovered services
+        let report = self.fetch_all(ids).await?;
+        for f in &report.failures {
+            warn!("Failed to fetch service {}: {:?}", f.id, f.error);
         }
 
-        Ok(services)
+        // Services are unique by ID, with the current primary page for each
+        Ok(report.services)
+    }
+
+    /// Discover local IoT services, yielding services as they are located.
+    /// Discovery requests are repeated until the discovery window elapses.
+    pub fn discover_stream(
+        &mut self,
+        opts: DiscoverOptions,
+    ) -> impl Stream<Item = Result<(ServiceInfo, DataInfo<Vec<EpDescriptor>>), IotError>> + '_ {
+        let deadline = tokio::time::Instant::now() + *opts.window;
+        let state = (self, opts, Vec::new(), VecDeque::new(), false);
+
+        stream::unfold(state, move |(c, opts, mut seen, mut pending, mut done)| {
+            async move {
+                loop {
+                    // Load information for located services
+                    if let Some(id) = pending.pop_front() {
+                        let r = c
+                            .info(InfoOptions {
+                                service: ServiceIdentifier::id(id),
+                            })
+                            .await;
+                        return Some((r, (c, opts, seen, pending, done)));
+                    }
+
+                    let now = tokio::time::Instant::now();
+                    if done || now >= deadline {
+                        return None;
+                    }
+
+                    let req = match discover_request(&opts) {
+                        Ok(r) => r,
+                        Err(e) => return Some((Err(e), (c, opts, seen, pending, true))),
+                    };
+
+                    // Issue discovery request, bounded by the remaining window
+                    match tokio::time::timeout(deadline - now, c.client.discover(req)).await {
+                        Ok(Ok(located)) => {
+                            for i in located {
+                                if !seen.contains(&i.id) {
+                                    seen.push(i.id.clone());
+                                    pending.push_back(i.id);
+                                }
+                            }
+                        }
+                        Ok(Err(e)) => return Some((Err(e.into()), (c, opts, seen, pending, true))),
+                        Err(_) => done = true,
+                    }
+
+                    // Wait before re-issuing requests
+                    if pending.is_empty() && !done {
+                        let now = tokio::time::Instant::now();
+                        tokio::time::sleep_until(deadline.min(now + DISCOVER_INTERVAL)).await;
+                    }
+                }
+            }
+        })
     }
 
     /// Register an existing service in the database
@@ -137,6 +362,17 @@ impl IotClient {
         &mut self,
         options: ListOptions,
     ) -> Result<Vec<(ServiceInfo, DataInfo<Vec<EpDescriptor>>)>, IotError> {
+        let report = self.list_report(options).await?;
+
+        for f in &report.failures {
+            warn!("Failed to fetch service {}: {:?}", f.id, f.error);
+        }
+
+        Ok(report.services)
+    }
+
+    /// List IoT services, reporting services that could not be fetched
+    pub async fn list_report(&mut self, options: ListOptions) -> Result<FetchReport, IotError> {
         let req = rpc::service::ServiceListOptions {
             application_id: Some(IoT::APPLICATION_ID),
             kind: Some(ServiceKind::Generic),
@@ -147,43 +383,35 @@ impl IotClient {
 
         debug!("Received service list: {:?}", services);
 
-        let mut iot_services = Vec::with_capacity(services.len());
-
-        for service_info in &services {
-            // Fetch page signature from service info
-            let page_sig = match &service_info.primary_page {
-                Some(p) => p,
-                None => {
-                    warn!("No primary page signature for service: {}", service_info.id);
-                    continue;
-                }
-            };
+        // Service information is already known, only pages need fetching
+        self.fetch_all(services).await
+    }
 
-            // Fetch page by signature
-            let (page_info, page) = self
-                .client
-                .object(rpc::FetchOptions {
-                    service: service_info.id.clone().into(),
-                    page_sig: page_sig.clone(),
-                })
-                .await?;
+    /// Fetch information for the provided services using up to `concurrency`
+    /// daemon connections, collecting per-service failures
+    pub async fn fetch_all<T: Into<FetchTarget>>(
+        &mut self,
+        services: Vec<T>,
+    ) -> Result<FetchReport, IotError> {
+        let targets: Vec<FetchTarget> = services.into_iter().map(|s| s.into()).collect();
+
+        // Open additional connections as required
+        let mut extra = vec![];
+        for _i in 1..self.concurrency.min(targets.len()) {
+            extra.push(Client::new(self.config.clone()).await?);
+        }
 
-            // Parse page info using iot application body
-            let page_info = match page_info.convert::<Vec<EpDescriptor>>() {
-                Ok(v) => v,
-                Err(e) => {
-                    warn!(
-                        "Failed to decode endpoints for service {}: {:?}",
-                        service_info.id, e
-                    );
-                    continue;
-                }
-            };
+        let clients = core::iter::once(&mut self.client)
+            .chain(extra.iter_mut())
+            .collect();
+        let report = fetch::fetch_all(clients, &self.pages, targets, self.progress).await;
 
-            iot_services.push((service_info.clone(), page_info));
+        for (s, p) in &report.services {
+            self.pages.insert(s.id.clone(), p.clone());
+            self.update_cache(s, p, &[]);
         }
 
-        Ok(iot_services)
+        Ok(report)
     }
 
     /// Fetch service information
@@ -200,6 +428,13 @@ impl IotClient {
             None => return Err(IotError::Client(Error::NoPageFound)),
         };
 
+        // Use the cached page if the primary page is unchanged
+        if let Some(p) = cached_page(&self.pages, &service_info).cloned() {
+            debug!("Using cached page for service {}", service_info.id);
+            self.update_cache(&service_info, &p, &[]);
+            return Ok((service_info, p));
+        }
+
         // Lookup page object
         let (page_info, _page) = self
             .client
@@ -221,9 +456,23 @@ impl IotClient {
             }
         };
 
+        self.pages
+            .insert(service_info.id.clone(), page_info.clone());
+        self.update_cache(&service_info, &page_info, &[]);
+
         Ok((service_info, page_info))
     }
 
+    /// Fetch a high-level handle for an IoT service
+    pub async fn handle(
+        &mut self,
+        service: ServiceIdentifier,
+    ) -> Result<IotServiceHandle<'_>, IotError> {
+        let (s, d) = self.info(InfoOptions { service }).await?;
+
+        Ok(IotServiceHandle::new(self, s, d))
+    }
+
     /// Publish raw data using an existing IoT service
     pub async fn publish_raw(
         &mut self,
@@ -269,6 +518,121 @@ impl IotClient {
         Ok(resp.map(|_d| ()))
     }
 
+    /// Subscribe to multiple IoT services, yielding the index of the updated
+    /// service (in `services`) for each received update
+    pub async fn subscribe_all(
+        &mut self,
+        services: &[ServiceIdentifier],
+    ) -> Result<impl Stream<Item = usize>, IotError> {
+        let mut updates = Vec::with_capacity(services.len());
+
+        for (i, s) in services.iter().enumerate() {
+            let u = self
+                .subscribe(rpc::SubscribeOptions { service: s.clone() })
+                .await?;
+
+            updates.push(u.map(move |_| i).boxed_local());
+        }
+
+        Ok(stream::select_all(updates))
+    }
+
+    /// Subscribe to data from an IoT service, reconnecting and resubscribing
+    /// (with [`SubscriptionEvent::Reconnected`] signalling the gap) if the
+    /// daemon connection is lost.
+    ///
+    /// This uses a dedicated daemon connection, so does not block other requests.
+    pub fn subscribe_reconnect(
+        &self,
+        service: ServiceIdentifier,
+    ) -> impl Stream<Item = Result<SubscriptionEvent, IotError>> {
+        reconnect::subscribe(
+            self.config.clone(),
+            rpc::SubscribeOptions { service },
+            self.retry.clone(),
+        )
+    }
+
+    /// Subscribe to an IoT service by name, resolving the service via the
+    /// provided nameservice and yielding the latest data object on each update.
+    ///
+    /// Returns the resolved service information and endpoint descriptors with
+    /// the update stream. Data is fetched using a dedicated daemon connection,
+    /// so the subscription does not block other requests.
+    pub async fn subscribe_by_name(
+        &mut self,
+        ns: ServiceIdentifier,
+        name: &str,
+    ) -> Result<
+        (
+            ServiceInfo,
+            DataInfo<Vec<EpDescriptor>>,
+            impl Stream<Item = Result<DataInfo<Vec<EpData>>, IotError>>,
+        ),
+        IotError,
+    > {
+        // Resolve service by name, names must map to a single service
+        let (_i, mut services) = self
+            .ns_search(NsSearchOptions {
+                ns,
+                name: Some(name.to_string()),
+                endpoints: vec![],
+                options: vec![],
+            })
+            .await?;
+
+        let (info, page) = match services.len() {
+            0 => return Err(IotError::NoService),
+            1 => services.remove(0),
+            count => return Err(IotError::AmbiguousService { count }),
+        };
+
+        debug!("Resolved name {} to service {}", name, info.id);
+
+        let service = ServiceIdentifier::id(info.id.clone());
+        let updates = self
+            .subscribe(rpc::SubscribeOptions {
+                service: service.clone(),
+            })
+            .await?
+            .boxed_local();
+        let q = IotClient::new(self.config.clone()).await?;
+
+        // Fetch the latest data object on update, skipping already seen objects
+        let data = stream::unfold((updates, q, None), move |(mut updates, mut q, mut last)| {
+            let service = service.clone();
+
+            async move {
+                loop {
+                    updates.next().await?;
+
+                    let bounds = PageBounds {
+                        count: Some(1),
+                        ..Default::default()
+                    };
+                    let o = QueryOptions {
+                        service: service.clone(),
+                        bounds,
+                    };
+                    let d = match q.query(o).await {
+                        Ok((_s, _p, mut d)) => d.drain(..).next(),
+                        Err(e) => return Some((Err(e), (updates, q, last))),
+                    };
+
+                    match d {
+                        Some(d) if Some(d.index) != last => {
+                            last = Some(d.index);
+                            return Some((Ok(d), (updates, q, last)));
+                        }
+                        _ => debug!("No new data for service {:?}", service),
+                    }
+                }
+            }
+        });
+
+        Ok((info, page, data))
+    }
+
     /// Query for data from an IoT service
     pub async fn query(
         &mut self,
@@ -291,21 +655,171 @@ impl IotClient {
 
         debug!("info: {:?}", iot_info);
 
-        let mut data_info = self.client.data(options).await?;
+        let service = options.service.clone();
+        let mut cursor = PageCursor::new(options.bounds.offset.unwrap_or(0));
+
+        let data_info = self.client.data(options).await?;
 
         // Filter and convert data objects
-        let iot_data = data_info
-            .drain(..)
-            .filter_map(|(i, _c)| {
-                if i.kind.is_page() {
-                    return None;
+        let mut iot_data = cursor.next(data_info, convert_data);
+        let n = iot_data.len();
+
+        // Fetch preceding objects until a full object is available for differential objects
+        while needs_baseline(&iot_data) {
+            let options = QueryOptions {
+                service: service.clone(),
+                bounds: PageBounds {
+                    count: Some(DELTA_PAGE_SIZE),
+                    offset: Some(cursor.offset),
+                },
+            };
+
+            let data_info = self.client.data(options).await?;
+            if data_info.is_empty() {
+                break;
+            }
+
+            iot_data.extend(cursor.next(data_info, convert_data));
+        }
+
+        // Reconstruct full values for differential objects
+        let unresolved = resolve_deltas(iot_data.iter_mut().filter_map(|d| match &mut d.body {
+            MaybeEncrypted::Cleartext(b) => Some(b),
+            _ => None,
+        }));
+        iot_data.truncate(n);
+
+        // Drop differential objects that could not be resolved, as these
+        // do not correspond positionally with service endpoints
+        if unresolved > 0 {
+            warn!(
+                "No full data object available for {} differential objects",
+                unresolved
+            );
+            iot_data.retain(|d| !is_unresolved(d));
+        }
+
+        self.update_cache(&iot_info.0, &iot_info.1, &iot_data);
+
+        Ok((iot_info.0, iot_info.1, iot_data))
+    }
+
+    /// Stream data objects for an IoT service, fetching `page_size` objects per request
+    /// (minimum 1) so large histories can be processed without loading them at once
+    pub fn query_stream(
+        &mut self,
+        service: ServiceIdentifier,
+        page_size: usize,
+    ) -> impl Stream<Item = Result<DataInfo<Vec<EpData>>, IotError>> + '_ {
+        let page_size = page_size.max(1);
+        let state = (self, PageCursor::new(0), VecDeque::new(), false);
+
+        stream::unfold(state, move |(c, mut cursor, mut pending, mut done)| {
+            let service = service.clone();
+
+            async move {
+                loop {
+                    // Return buffered objects, fetching older objects where required
+                    // to resolve differential objects
+                    match pending.front() {
+                        Some(d) if is_unresolved(d) && !done => (),
+                        Some(d) if is_unresolved(d) => {
+                            warn!(
+                                "No full data object for differential object {}, skipping",
+                                d.index
+                            );
+                            pending.pop_front();
+                            continue;
+                        }
+                        Some(_) => {
+                            let d = pending.pop_front().unwrap();
+                            return Some((Ok(d), (c, cursor, pending, done)));
+                        }
+                        None if done => return None,
+                        None => (),
+                    }
+
+                    // Fetch the next page of objects
+                    let options = QueryOptions {
+                        service: service.clone(),
+                        bounds: PageBounds {
+                            count: Some(page_size),
+                            offset: Some(cursor.offset),
+                        },
+                    };
+
+                    let data_info = match c.client.data(options).await {
+                        Ok(d) => d,
+                        Err(e) => return Some((Err(e.into()), (c, cursor, pending, true))),
+                    };
+
+                    // Short pages indicate the end of available data
+                    done = data_info.len() < page_size;
+
+                    pending.extend(cursor.next(data_info, convert_data));
+
+                    // Reconstruct full values for differential objects
+                    resolve_deltas(pending.iter_mut().filter_map(|d| match &mut d.body {
+                        MaybeEncrypted::Cleartext(b) => Some(b),
+                        _ => None,
+                    }));
                 }
+            }
+        })
+    }
 
-                i.convert::<Vec<EpData>>().ok()
+    /// Audit stored objects for a service, verifying object signatures against the
+    /// service public key and previous-signature links, and reporting missing or
+    /// conflicting objects
+    pub async fn audit(
+        &mut self,
+        service: ServiceIdentifier,
+    ) -> Result<AuditReport<Signature>, IotError> {
+        let (_h, info) = self
+            .client
+            .info(InfoOptions {
+                service: service.clone(),
             })
-            .collect();
+            .await?;
 
-        Ok((iot_info.0, iot_info.1, iot_data))
+        // Walk raw objects (including pages, which share the signature chain),
+        // checking signatures on the stored containers rather than daemon decoded info
+        let mut items = vec![];
+        let mut offset = 0;
+        loop {
+            let options = QueryOptions {
+                service: service.clone(),
+                bounds: PageBounds {
+                    count: Some(AUDIT_PAGE_SIZE),
+                    offset: Some(offset),
+                },
+            };
+
+            let data_info = self.client.data(options).await?;
+            let n = data_info.len();
+            offset += n;
+
+            for (i, c) in data_info {
+                let previous = c.public_options_iter().find_map(|o| match o {
+                    Options::PrevSig(s) => Some(s),
+                    _ => None,
+                });
+
+                items.push(AuditItem {
+                    index: i.index as u64,
+                    signature: c.signature(),
+                    previous,
+                    verified: c.verify(&info.public_key).unwrap_or(false),
+                });
+            }
+
+            // Short pages indicate the end of available data
+            if n < AUDIT_PAGE_SIZE {
+                break;
+            }
+        }
+
+        Ok(audit::audit(items))
     }
 
     /// Register an IoT service with the specified nameservice
@@ -360,29 +874,54 @@ impl IotClient {
     pub async fn ns_search(
         &mut self,
         opts: NsSearchOptions,
-    ) -> Result<(NsSearchInfo, Vec<(ServiceInfo, DataInfo<Vec<EpDescriptor>>)>), IotError> {
+    ) -> Result<
+        (
+            NsSearchInfo,
+            Vec<(ServiceInfo, DataInfo<Vec<EpDescriptor>>)>,
+        ),
+        IotError,
+    > {
         debug!("Searching via nameservice: {:?}", opts.ns);
 
-        // Resolve endpoint kind to hash if required
-        let hash = match opts.endpoint {
-            Some(e) => {
-                let v = u16::from(&e);
-                Some(Crypto::hash(&v.to_le_bytes()).unwrap())
-            }
-            None => None,
+        // Build a search per criterion, with endpoint kinds resolved to hashes
+        let search = |name, options, hash| rpc::NsSearchOptions {
+            ns: opts.ns.clone(),
+            name,
+            options,
+            hash,
+            no_persist: false,
         };
 
-        // Perform search with nameservice
-        let locate_info = self
-            .client
-            .ns_search(rpc::NsSearchOptions {
-                ns: opts.ns,
-                name: opts.name.clone(),
-                options: opts.options.clone(),
-                hash: hash,
-                no_persist: false,
-            })
-            .await?;
+        let mut searches = vec![];
+        if let Some(n) = &opts.name {
+            searches.push(search(Some(n.clone()), None, None));
+        }
+        for e in &opts.endpoints {
+            let v = u16::from(e);
+            let hash = Crypto::hash(&v.to_le_bytes()).unwrap();
+            searches.push(search(None, None, Some(hash)));
+        }
+        for o in &opts.options {
+            searches.push(search(None, Some(o.clone()), None));
+        }
+        if searches.is_empty() {
+            return Err(IotError::NoCriteria);
+        }
+
+        // Perform searches with nameservice, matching services must satisfy all criteria
+        let mut results = vec![];
+        for s in searches {
+            results.push(self.client.ns_search(s).await?);
+        }
+
+        let ids = intersect(
+            results
+                .iter()
+                .map(|r| r.matches.iter().map(|m| m.id.clone()).collect()),
+        );
+
+        let mut locate_info = results.swap_remove(0);
+        locate_info.matches.retain(|m| ids.contains(&m.id));
 
         // Load information for discovered services
         let mut services = vec![];
@@ -417,5 +956,204 @@ impl IotClient {
     }
 }
 
+/// Page size for fetching data objects during history audits
+const AUDIT_PAGE_SIZE: usize = 64;
+
+/// Page size for fetching preceding objects to resolve differential data objects
+const DELTA_PAGE_SIZE: usize = 16;
+
+/// Interval between discovery requests when streaming discovery results
+const DISCOVER_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Build a discovery request with endpoint and option filters
+fn discover_request(opts: &DiscoverOptions) -> Result<rpc::DiscoverOptions, IotError> {
+    let eps: Vec<_> = opts
+        .endpoints
+        .iter()
+        .map(|k| EpDescriptor::new(*k, EpFlags::empty()))
+        .collect();
+    let (body, _) = eps.encode_vec()?;
+
+    Ok(rpc::DiscoverOptions {
+        application_id: 1,
+        body: Some(body),
+        filters: opts.options.to_vec(),
+    })
+}
+
+/// Deduplicate items by key, retaining the first-seen item for each key
+fn dedup_first<T, K: PartialEq>(
+    items: impl IntoIterator<Item = T>,
+    key: impl Fn(&T) -> K,
+) -> Vec<T> {
+    let mut out: Vec<T> = vec![];
+
+    for i in items {
+        if !out.iter().any(|o| key(o) == key(&i)) {
+            out.push(i);
+        }
+    }
+
+    out
+}
+
+/// Intersect sets of items, retaining the order of the first set
+fn intersect<T: PartialEq>(sets: impl IntoIterator<Item = Vec<T>>) -> Vec<T> {
+    let mut sets = sets.into_iter();
+
+    let mut out = match sets.next() {
+        Some(s) => s,
+        None => return vec![],
+    };
+
+    for s in sets {
+        out.retain(|i| s.contains(i));
+    }
+
+    out
+}
+
+/// Pagination state for data queries
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PageCursor {
+    /// Offset of the next page
+    offset: usize,
+}
+
+impl PageCursor {
+    fn new(offset: usize) -> Self {
+        Self { offset }
+    }
+
+    /// Convert a fetched page, advancing by the raw page length as pages and
+    /// objects that fail to decode still occupy positions in the history
+    fn next<R, T>(&mut self, page: Vec<R>, convert: impl FnOnce(Vec<R>) -> Vec<T>) -> Vec<T> {
+        self.offset += page.len();
+        convert(page)
+    }
+}
+
+/// Filter pages and convert data objects to IoT data
+fn convert_data<C>(mut data_info: Vec<(DataInfo, C)>) -> Vec<DataInfo<Vec<EpData>>> {
+    data_info
+        .drain(..)
+        .filter_map(|(i, _c)| {
+            if i.kind.is_page() {
+                return None;
+            }
+
+            i.convert::<Vec<EpData>>().ok()
+        })
+        .collect()
+}
+
+/// Check whether the oldest readable object (of objects ordered newest first)
+/// is a differential object, requiring preceding objects to resolve
+fn needs_baseline(objects: &[DataInfo<Vec<EpData>>]) -> bool {
+    objects
+        .iter()
+        .rev()
+        .find_map(|d| match &d.body {
+            MaybeEncrypted::Cleartext(b) => Some(is_delta(b)),
+            _ => None,
+        })
+        .unwrap_or(false)
+}
+
+/// Check whether a data object is a differential object that has not been
+/// resolved against a full object
+fn is_unresolved(d: &DataInfo<Vec<EpData>>) -> bool {
+    matches!(&d.body, MaybeEncrypted::Cleartext(b) if is_delta(b))
+}
+
+/// Resolve differential data objects (ordered newest first) against the
+/// preceding full object, returning the number of unresolved objects
+fn resolve_deltas<'a>(objects: impl DoubleEndedIterator<Item = &'a mut Vec<EpData>>) -> usize {
+    let mut state: Option<Vec<EpData>> = None;
+    let mut unresolved = 0;
+
+    for d in objects.rev() {
+        if !is_delta(d) {
+            state = Some(d.clone());
+            continue;
+        }
+
+        match state.as_mut().map(|s| apply_delta(s, d)) {
+            Some(Ok(())) => *d = state.clone().unwrap_or_default(),
+            _ => unresolved += 1,
+        }
+    }
+
+    unresolved
+}
+
 #[cfg(test)]
-mod test {}
+mod test {
+    use super::*;
+
+    #[test]
+    fn dedup_discovered() {
+        let located = vec![("b", 1), ("a", 3), ("b", 4), ("a", 2), ("c", 0)];
+
+        let services = dedup_first(located, |(id, _v)| *id);
+        assert_eq!(services, vec![("b", 1), ("a", 3), ("c", 0)]);
+    }
+
+    #[test]
+    fn page_offsets() {
+        // Stored history, with an object that fails to decode
+        let history = [Some(5), Some(4), None, Some(2), Some(1), Some(0)];
+        let decode = |p: Vec<Option<u32>>| p.into_iter().flatten().collect::<Vec<_>>();
+
+        let mut cursor = PageCursor::new(0);
+        let mut objects = vec![];
+        loop {
+            let end = (cursor.offset + 3).min(history.len());
+            let page = history[cursor.offset..end].to_vec();
+            if page.is_empty() {
+                break;
+            }
+            objects.extend(cursor.next(page, decode));
+        }
+
+        // Each object is returned once, with no overlapping pages
+        assert_eq!(objects, vec![5, 4, 2, 1, 0]);
+        assert_eq!(cursor.offset, history.len());
+    }
+
+    #[test]
+    fn intersect_criteria() {
+        let temperature = vec![1, 2, 3, 4];
+        let humidity = vec![4, 3, 5];
+        let bedroom = vec![3, 4, 6];
+
+        assert_eq!(intersect(vec![temperature, humidity, bedroom]), vec![3, 4]);
+        assert_eq!(intersect(Vec::<Vec<u8>>::new()), vec![]);
+    }
+
+    #[test]
+    fn resolve_differential_objects() {
+        use crate::endpoint::EpValue;
+
+        let v = |t, s| {
+            vec![
+                EpData::new(EpValue::Float32(t)),
+                EpData::new(EpValue::Bool(s)),
+            ]
+        };
+
+        // Objects ordered newest first, with a delta preceding the first full object
+        let mut objects = vec![
+            vec![EpData::new(EpValue::Bool(true)).with_index(1)],
+            vec![EpData::new(EpValue::Float32(21.5)).with_index(0)],
+            v(21.0, false),
+            vec![EpData::new(EpValue::Float32(20.0)).with_index(0)],
+        ];
+
+        assert_eq!(resolve_deltas(objects.iter_mut()), 1);
+        assert_eq!(objects[0], v(21.5, true));
+        assert_eq!(objects[1], v(21.5, false));
+        assert_eq!(objects[2], v(21.0, false));
+        assert!(is_delta(&objects[3]));
+    }
+}
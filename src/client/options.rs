@@ -3,8 +3,6 @@ use core::convert::TryInto;
 #[cfg(feature = "alloc")]
 use alloc::vec::Vec;
 
-use bytes::BytesMut;
-
 use clap::{Parser, Subcommand};
 use dsf_core::{api::Application, types::Id};
 
@@ -13,17 +11,27 @@ use dsf_core::keys::Keys;
 use dsf_core::options::Options;
 use dsf_core::types::PageKind;
 
-pub use dsf_rpc::service::{try_parse_key_value, LocateOptions, RegisterOptions, SubscribeOptions, ServiceListOptions};
+pub use dsf_rpc::service::{
+    try_parse_key_value, LocateOptions, RegisterOptions, ServiceListOptions, SubscribeOptions,
+};
 use dsf_rpc::ServiceIdentifier;
 
 use crate::{
+    client::{
+        layout::DEFAULT_BUFFER_LEN, parse_composite_source, parse_location, CompositeSource,
+        Location, RetryConfig, ValueFilter,
+    },
     endpoint::{
-        parse_endpoint_data, parse_endpoint_descriptor, EpData, EpDescriptor, EpKind, IotData,
+        parse_device_class, parse_endpoint_data, parse_endpoint_descriptor, parse_endpoint_value,
+        DeviceClass, EpData, EpDescriptor, EpKind, EpValue, CLASS_META_KEY, MAX_ENDPOINTS,
     },
     error::IotError,
     IoT,
 };
 
+/// Maximum encoded object body length, bodies must fit the default engine object buffer
+pub const MAX_BODY_LEN: usize = DEFAULT_BUFFER_LEN;
+
 #[derive(Debug, Clone, Subcommand)]
 pub enum Command {
     /// Create a new IOT service on this device
@@ -44,24 +52,24 @@ pub enum Command {
     /// Fetch information for a known IoT service
     Info(InfoOptions),
 
-    /// Subscribe to a known IoT service
-    Subscribe(SubscribeOptions),
+    /// Subscribe to one or more known IoT services
+    Subscribe(MultiSubscribeOptions),
 
     /// Query for data from a known IoT service
-    Data(QueryOptions),
+    Data(DataOptions),
 
     /// List known IoT services
-    List(ListOptions),
+    List(ListServicesOptions),
 
     /// Generate a service ID / key for manual loading
     GenKeys,
 
     /// Helper to encode IoT data objects
-    #[clap(hide=true)]
+    #[clap(hide = true)]
     Encode(EncodeOptions),
 
     /// Helper to decode IoT data objects
-    #[clap(hide=true)]
+    #[clap(hide = true)]
     Decode(DecodeOptions),
 
     /// Register an IoT service using a provided Name Service
@@ -69,10 +77,217 @@ pub enum Command {
 
     /// Search for an IoT service using a Name Service
     NsSearch(NsSearchOptions),
+
+    /// Watch an IoT service endpoint, executing a command on matching values
+    Watch(WatchOptions),
+
+    /// Manage additional endpoint kinds
+    #[clap(subcommand)]
+    Kinds(KindsCommand),
+
+    /// Export latest IoT service values for prometheus
+    Exporter(ExporterOptions),
+
+    /// Run a composite service combining endpoints from other services
+    Composite(CompositeOptions),
+
+    /// Audit stored data for a service, verifying object signatures and links
+    /// and reporting missing or conflicting objects
+    Audit(AuditOptions),
+
+    /// Manage service identities (keys)
+    #[clap(subcommand)]
+    Keys(KeysCommand),
+
+    /// Create services for devices listed in a CSV manifest, writing encrypted key files
+    Provision(ProvisionOptions),
+}
+
+#[derive(Debug, Clone, Parser)]
+pub struct ProvisionOptions {
+    /// Device manifest (CSV, `name,room,endpoints` with `;` separated endpoints)
+    #[clap(long)]
+    pub manifest: String,
+
+    /// Directory for per-device key files (`NAME.key`), defaults to the
+    /// profile key directory or the current directory
+    #[clap(long)]
+    pub out_dir: Option<String>,
+
+    /// Passphrase for key file encryption
+    #[clap(long, env = "DSF_IOT_PASSPHRASE")]
+    pub passphrase: String,
+
+    /// Indicate services should be public (unencrypted)
+    #[clap(short)]
+    pub public: bool,
+}
+
+#[derive(Debug, Clone, Parser)]
+pub struct AuditOptions {
+    #[clap(flatten)]
+    pub service: ServiceIdentifier,
+}
+
+#[derive(Debug, Clone, Parser)]
+pub struct MultiSubscribeOptions {
+    /// Service IDs to subscribe to
+    #[clap(short = 'i', long = "id")]
+    pub ids: Vec<Id>,
+
+    /// Service indices to subscribe to
+    #[clap(short = 'n', long = "index")]
+    pub indices: Vec<usize>,
+
+    /// Exit after receiving the provided number of updates
+    #[clap(long)]
+    pub count: Option<usize>,
+
+    /// Exit after the provided duration
+    #[clap(long)]
+    pub duration: Option<humantime::Duration>,
+
+    /// Reconnect and resubscribe if the daemon connection is lost
+    #[clap(long)]
+    pub reconnect: bool,
+
+    /// Maximum consecutive reconnection attempts (unlimited if not set)
+    #[clap(long, requires = "reconnect")]
+    pub retry_attempts: Option<usize>,
+
+    /// Maximum backoff between reconnection attempts
+    #[clap(long, default_value = "30s", requires = "reconnect")]
+    pub retry_max: humantime::Duration,
+}
+
+impl MultiSubscribeOptions {
+    /// Build reconnection retry configuration
+    pub fn retry(&self) -> RetryConfig {
+        RetryConfig {
+            max_attempts: self.retry_attempts,
+            max: *self.retry_max,
+            ..Default::default()
+        }
+    }
+
+    /// Fetch identifiers for the requested services
+    pub fn services(&self) -> Vec<ServiceIdentifier> {
+        let ids = self.ids.iter().map(|id| ServiceIdentifier::id(id.clone()));
+        let indices = self.indices.iter().map(|i| ServiceIdentifier {
+            id: None,
+            index: Some(*i),
+        });
+
+        ids.chain(indices).collect()
+    }
+}
+
+#[derive(Debug, Clone, Parser)]
+pub struct CompositeOptions {
+    /// Existing composite service to publish to (a new service is created if not provided)
+    #[clap(long)]
+    pub service: Option<Id>,
+
+    /// Source services and endpoints, in the form `ID=KIND[,KIND...]`
+    #[clap(long, required = true, value_parser=parse_composite_source)]
+    pub source: Vec<CompositeSource>,
+
+    #[clap(short)]
+    /// Indicate a new composite service should be public (unencrypted)
+    pub public: bool,
+}
+
+#[derive(Debug, Clone, Parser)]
+pub struct ExporterOptions {
+    /// Address to listen for prometheus scrape requests
+    #[clap(long, default_value = "0.0.0.0:9100")]
+    pub listen: std::net::SocketAddr,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum KindsCommand {
+    /// List available endpoint kinds
+    List,
+
+    /// Register an additional endpoint kind, persisted in the kinds file
+    Register(RegisterKindOptions),
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum KeysCommand {
+    /// Generate a new service identity, optionally writing an encrypted key file
+    Generate(GenerateKeysOptions),
+
+    /// Encrypt a service identity (JSON, as printed by generate) to a key file
+    Export(ExportKeysOptions),
+
+    /// Decrypt a service identity from a key file
+    Import(ImportKeysOptions),
+}
+
+#[derive(Debug, Clone, Parser)]
+pub struct GenerateKeysOptions {
+    /// Encrypted key file to write (identity is printed in the clear if not set)
+    #[clap(long)]
+    pub file: Option<String>,
+
+    /// Passphrase for key file encryption
+    #[clap(long, env = "DSF_IOT_PASSPHRASE", requires = "file")]
+    pub passphrase: Option<String>,
+}
+
+#[derive(Debug, Clone, Parser)]
+pub struct ExportKeysOptions {
+    /// Service identity file (JSON)
+    #[clap(long)]
+    pub input: String,
+
+    /// Encrypted key file to write
+    #[clap(long)]
+    pub file: String,
+
+    /// Passphrase for key file encryption
+    #[clap(long, env = "DSF_IOT_PASSPHRASE")]
+    pub passphrase: String,
+}
+
+#[derive(Debug, Clone, Parser)]
+pub struct ImportKeysOptions {
+    /// Encrypted key file to read
+    #[clap(long)]
+    pub file: String,
+
+    /// Passphrase for key file decryption
+    #[clap(long, env = "DSF_IOT_PASSPHRASE")]
+    pub passphrase: String,
+
+    /// Write the decrypted identity (JSON) to a file rather than stdout
+    #[clap(long)]
+    pub output: Option<String>,
+}
+
+#[derive(Debug, Clone, Parser)]
+pub struct RegisterKindOptions {
+    /// Endpoint kind ID
+    #[clap(long)]
+    pub id: u16,
+
+    /// Endpoint kind name
+    #[clap(long)]
+    pub name: String,
+
+    /// Endpoint unit
+    #[clap(long)]
+    pub unit: String,
 }
 
 #[derive(Debug, Clone, Parser)]
 pub struct CreateOptions {
+    /// Standard device class (environment-sensor, smart-light, smart-plug, door-sensor),
+    /// class endpoints precede any additional endpoints
+    #[clap(long, value_parser=parse_device_class)]
+    pub class: Option<DeviceClass>,
+
     /// Service endpoint information
     #[clap(long, value_parser=parse_endpoint_descriptor)]
     pub endpoints: Vec<EpDescriptor>,
@@ -81,6 +296,10 @@ pub struct CreateOptions {
     #[clap(long, value_parser=try_parse_key_value)]
     pub meta: Vec<(String, String)>,
 
+    /// Service location (LAT,LNG[,ALT]), published as service metadata
+    #[clap(long, value_parser=parse_location)]
+    pub location: Option<Location>,
+
     #[clap(short)]
     /// Indicate the service should be public (unencrypted)
     pub public: bool,
@@ -88,33 +307,75 @@ pub struct CreateOptions {
     #[clap(long)]
     /// Indicate the service should be registered and replicated following creation
     pub register: bool,
+
+    /// Maximum number of endpoints, this must match the endpoint capacity (`N`)
+    /// of the engines serving the service (see [`crate::IotApp`])
+    #[clap(long, default_value_t = MAX_ENDPOINTS)]
+    pub max_endpoints: usize,
 }
 
 impl Default for CreateOptions {
     fn default() -> Self {
         Self {
+            class: None,
             endpoints: vec![],
             meta: vec![],
+            location: None,
             public: false,
             register: true,
+            max_endpoints: MAX_ENDPOINTS,
         }
     }
 }
 
+impl CreateOptions {
+    /// Fetch service endpoint descriptors, with device class endpoints
+    /// preceding additional endpoints
+    pub fn descriptors(&self) -> Vec<EpDescriptor> {
+        let mut endpoints = match &self.class {
+            Some(c) => c.descriptors().to_vec(),
+            None => vec![],
+        };
+        endpoints.extend_from_slice(&self.endpoints);
+        endpoints
+    }
+}
+
 impl TryInto<dsf_rpc::CreateOptions> for CreateOptions {
     type Error = IotError;
 
     // Generate an RPC create message for an IoT service instance
     fn try_into(self) -> Result<dsf_rpc::CreateOptions, Self::Error> {
-        let n = self.endpoints.encode_len()?;
+        let endpoints = self.descriptors();
+        if endpoints.is_empty() {
+            return Err(IotError::EmptyData);
+        }
+
+        // Check endpoints fit the target IoT info object prior to contacting the daemon
+        if endpoints.len() > self.max_endpoints {
+            return Err(IotError::TooManyEndpoints {
+                count: endpoints.len(),
+                max: self.max_endpoints,
+            });
+        }
+
+        let n = body_len(endpoints.encode_len()?)?;
         let mut body = vec![0u8; n];
-        let n = self.endpoints.encode(&mut body[..])?;
+        let n = endpoints.encode(&mut body[..])?;
+
+        let mut metadata = self.meta.clone();
+        if let Some(c) = &self.class {
+            metadata.push((CLASS_META_KEY.to_string(), c.to_string()));
+        }
+        if let Some(l) = &self.location {
+            metadata.extend(l.metadata());
+        }
 
         let co = dsf_rpc::CreateOptions {
             application_id: IoT::APPLICATION_ID,
             page_kind: Some(PageKind::Generic),
             body: Some(body[..n].to_vec()),
-            metadata: self.meta.clone(),
+            metadata,
             public: self.public,
             register: self.register,
             ..Default::default()
@@ -136,6 +397,11 @@ pub struct PublishOptions {
     /// Measurement metadata
     #[clap(long, value_parser=try_parse_key_value)]
     pub meta: Vec<(String, String)>,
+
+    /// Maximum number of values, this must match the endpoint capacity (`N`)
+    /// of the engines serving the service (see [`crate::IotApp`])
+    #[clap(long, default_value_t = MAX_ENDPOINTS)]
+    pub max_endpoints: usize,
 }
 
 impl TryInto<dsf_rpc::PublishOptions> for PublishOptions {
@@ -143,11 +409,21 @@ impl TryInto<dsf_rpc::PublishOptions> for PublishOptions {
 
     // Generate an RPC create message for an IoT service instance
     fn try_into(self) -> Result<dsf_rpc::PublishOptions, Self::Error> {
-        let mut body = BytesMut::new();
+        if self.data.is_empty() {
+            return Err(IotError::EmptyData);
+        }
 
-        let data = IotData::<8>::new(&self.data).map_err(|_| IotError::Overrun)?;
+        // Check values fit the target IoT data object prior to contacting the daemon
+        if self.data.len() > self.max_endpoints {
+            return Err(IotError::TooManyEndpoints {
+                count: self.data.len(),
+                max: self.max_endpoints,
+            });
+        }
 
-        let n = data.encode(&mut body)?;
+        let n = body_len(self.data.encode_len()?)?;
+        let mut body = vec![0u8; n];
+        let n = self.data.encode(&mut body[..])?;
 
         let po = dsf_rpc::PublishOptions {
             service: self.service,
@@ -159,12 +435,46 @@ impl TryInto<dsf_rpc::PublishOptions> for PublishOptions {
     }
 }
 
+/// Check an encoded body fits within [`MAX_BODY_LEN`]
+fn body_len(needed: usize) -> Result<usize, IotError> {
+    match needed <= MAX_BODY_LEN {
+        true => Ok(needed),
+        false => Err(IotError::EncodeOverflow { needed }),
+    }
+}
+
 /// QueryOptions used to fetch data for an IoT service
 pub type QueryOptions = dsf_rpc::data::DataListOptions;
 
+/// Query data for an IoT service, optionally summarising endpoint values
+#[derive(Debug, Clone, Parser)]
+pub struct DataOptions {
+    #[clap(flatten)]
+    pub query: QueryOptions,
+
+    /// Print per-endpoint statistics (count, min, max, mean, stddev) over the queried objects
+    #[clap(long)]
+    pub stats: bool,
+}
+
 /// ListOptions used to list known iot services
 pub type ListOptions = dsf_rpc::service::ServiceListOptions;
 
+/// List known IoT services, optionally filtering by location
+#[derive(Debug, Clone, Parser)]
+pub struct ListServicesOptions {
+    #[clap(flatten)]
+    pub list: ListOptions,
+
+    /// Only list services located near this location (LAT,LNG)
+    #[clap(long, value_parser=parse_location)]
+    pub near: Option<Location>,
+
+    /// Radius for `--near` filtering in kilometres
+    #[clap(long, default_value = "1.0")]
+    pub radius: f32,
+}
+
 /// InfoOptions used to fetch info for services
 pub type InfoOptions = dsf_rpc::service::InfoOptions;
 
@@ -212,6 +522,10 @@ pub struct DiscoverOptions {
     /// Options for filtering
     #[clap(long)]
     pub options: Vec<Options>,
+
+    /// Discovery window, results are shown as services respond
+    #[clap(long, default_value = "3s")]
+    pub window: humantime::Duration,
 }
 
 #[derive(Debug, Clone, Parser)]
@@ -234,14 +548,182 @@ pub struct NsSearchOptions {
     pub ns: ServiceIdentifier,
 
     /// Service name filter
-    #[clap(long, group = "filters")]
+    #[clap(long)]
     pub name: Option<String>,
 
-    /// Endpoint filter
-    #[clap(long, group = "filters")]
-    pub endpoint: Option<EpKind>,
+    /// Endpoint filters, matching services must have all endpoints
+    #[clap(long = "endpoint")]
+    pub endpoints: Vec<EpKind>,
 
-    /// Option filter
-    #[clap(long, group = "filters")]
-    pub options: Option<Options>,
+    /// Option filters (e.g. room), matching services must have all options
+    #[clap(long)]
+    pub options: Vec<Options>,
+}
+
+#[derive(Debug, Clone, Parser)]
+pub struct WatchOptions {
+    #[clap(flatten)]
+    pub service: ServiceIdentifier,
+
+    /// Endpoint to watch
+    #[clap(long)]
+    pub endpoint: EpKind,
+
+    /// Match values above the provided threshold
+    #[clap(long)]
+    pub above: Option<f32>,
+
+    /// Match values below the provided threshold
+    #[clap(long)]
+    pub below: Option<f32>,
+
+    /// Match values equal to the provided value
+    #[clap(long, value_parser=parse_endpoint_value)]
+    pub equals: Option<EpValue>,
+
+    /// Command to execute on matching values (optionally prefixed with `exec`),
+    /// event information is passed via DSF_IOT_* environment variables
+    #[clap(last = true, required = true)]
+    pub exec: Vec<String>,
+}
+
+impl WatchOptions {
+    /// Build a value filter from watch options
+    pub fn filter(&self) -> ValueFilter {
+        ValueFilter {
+            above: self.above,
+            below: self.below,
+            equals: self.equals.clone(),
+        }
+    }
+
+    /// Fetch the command to be executed
+    pub fn command(&self) -> &[String] {
+        match self.exec.first().map(|s| s.as_str()) {
+            Some("exec") => &self.exec[1..],
+            _ => &self.exec[..],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoint::EpFlags;
+
+    #[test]
+    fn create_validation() {
+        let o = CreateOptions::default();
+        let r: Result<dsf_rpc::CreateOptions, _> = o.try_into();
+        assert!(matches!(r, Err(IotError::EmptyData)));
+
+        let o = CreateOptions {
+            endpoints: vec![EpDescriptor::new(EpKind::Temperature, EpFlags::R)],
+            ..Default::default()
+        };
+        let r: Result<dsf_rpc::CreateOptions, _> = o.try_into();
+        assert!(r.is_ok());
+
+        // Class endpoints count towards the configured endpoint limit
+        let o = CreateOptions {
+            class: Some(DeviceClass::SmartLight),
+            endpoints: vec![EpDescriptor::new(EpKind::Temperature, EpFlags::R); 2],
+            max_endpoints: 4,
+            ..Default::default()
+        };
+        let r: Result<dsf_rpc::CreateOptions, _> = o.clone().try_into();
+        assert!(matches!(
+            r,
+            Err(IotError::TooManyEndpoints { count: 5, max: 4 })
+        ));
+
+        // Larger limits allow services for engines with more endpoints
+        let o = CreateOptions {
+            endpoints: vec![EpDescriptor::new(EpKind::Temperature, EpFlags::R); 8],
+            max_endpoints: 16,
+            ..o
+        };
+        let r: Result<dsf_rpc::CreateOptions, _> = o.try_into();
+        assert!(r.is_ok());
+    }
+
+    #[test]
+    fn create_location() {
+        let o = CreateOptions {
+            endpoints: vec![EpDescriptor::new(EpKind::Temperature, EpFlags::R)],
+            meta: vec![("site".to_string(), "north".to_string())],
+            location: Some(Location::new(-36.5, 174.75, None).unwrap()),
+            ..Default::default()
+        };
+        let r: dsf_rpc::CreateOptions = o.try_into().unwrap();
+        assert_eq!(
+            r.metadata,
+            vec![
+                ("site".to_string(), "north".to_string()),
+                ("lat".to_string(), "-36.5".to_string()),
+                ("lng".to_string(), "174.75".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn create_class() {
+        let o = CreateOptions {
+            class: Some(DeviceClass::SmartLight),
+            endpoints: vec![EpDescriptor::new(EpKind::Temperature, EpFlags::R)],
+            ..Default::default()
+        };
+
+        let d = o.descriptors();
+        assert_eq!(d.len(), 4);
+        assert!(DeviceClass::SmartLight.matches(&d));
+        assert_eq!(d[3].kind, EpKind::Temperature);
+
+        let r: dsf_rpc::CreateOptions = o.try_into().unwrap();
+        assert_eq!(
+            r.metadata,
+            vec![("class".to_string(), "smart-light".to_string())]
+        );
+    }
+
+    #[test]
+    fn publish_validation() {
+        let o = PublishOptions {
+            service: ServiceIdentifier {
+                id: None,
+                index: Some(0),
+            },
+            data: vec![],
+            meta: vec![],
+            max_endpoints: 4,
+        };
+        let r: Result<dsf_rpc::PublishOptions, _> = o.clone().try_into();
+        assert!(matches!(r, Err(IotError::EmptyData)));
+
+        let o = PublishOptions {
+            data: vec![EpData::new(EpValue::from("0123456789abcdef")); 5],
+            ..o
+        };
+        let r: Result<dsf_rpc::PublishOptions, _> = o.clone().try_into();
+        assert!(matches!(
+            r,
+            Err(IotError::TooManyEndpoints { count: 5, max: 4 })
+        ));
+
+        let o = PublishOptions {
+            max_endpoints: 5,
+            ..o
+        };
+        let r: Result<dsf_rpc::PublishOptions, _> = o.clone().try_into();
+        assert!(r.is_ok());
+
+        let text = "0123456789abcdef".repeat(4);
+        let o = PublishOptions {
+            data: vec![EpData::new(EpValue::from(&text[..])); MAX_ENDPOINTS],
+            max_endpoints: MAX_ENDPOINTS,
+            ..o
+        };
+        let r: Result<dsf_rpc::PublishOptions, _> = o.try_into();
+        assert!(matches!(r, Err(IotError::EncodeOverflow { needed: 544 })));
+    }
 }
@@ -0,0 +1,103 @@
+//! Named client configuration profiles, allowing operators managing several
+//! sites or daemons to select connection and output settings with `--profile`.
+//!
+//! Profiles are loaded from a TOML file with one table per profile, for example:
+//!
+//! ```toml
+//! [site-a]
+//! daemon_socket = "/var/run/dsfd/site-a.sock"
+//! ns = "..."
+//! key_dir = "/etc/dsf-iot/site-a/keys"
+//! format = "senml"
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::IotError;
+
+/// Client configuration profile, unset fields use command defaults
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// Daemon socket address
+    pub daemon_socket: Option<String>,
+    /// Default nameservice ID for name registration and search
+    pub ns: Option<String>,
+    /// Default directory for service key files
+    pub key_dir: Option<String>,
+    /// Output format for data commands
+    pub format: Option<String>,
+    /// Unit system for data output
+    pub units: Option<String>,
+    /// Client cache file
+    pub cache: Option<String>,
+}
+
+impl Profile {
+    /// Build client arguments for profile settings, these are expected to
+    /// precede (and be overridden by) user provided arguments
+    pub fn args(&self) -> Vec<String> {
+        let mut args = vec![];
+
+        for (flag, v) in [
+            ("--daemon-socket", &self.daemon_socket),
+            ("--format", &self.format),
+            ("--units", &self.units),
+            ("--cache", &self.cache),
+        ] {
+            if let Some(v) = v {
+                args.push(flag.to_string());
+                args.push(v.clone());
+            }
+        }
+
+        args
+    }
+}
+
+/// Named client profiles
+pub type Profiles = BTreeMap<String, Profile>;
+
+/// Parse client profiles from a TOML string
+pub fn parse_profiles(s: &str) -> Result<Profiles, IotError> {
+    let p = toml::from_str(s)?;
+    Ok(p)
+}
+
+/// Load client profiles from a TOML file
+pub fn load_profiles<P: AsRef<Path>>(path: P) -> Result<Profiles, IotError> {
+    let s = std::fs::read_to_string(path)?;
+    parse_profiles(&s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_args() {
+        let p = parse_profiles(
+            r#"
+[site-a]
+daemon_socket = "/tmp/site-a.sock"
+format = "senml"
+
+[site-b]
+units = "imperial"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(p.len(), 2);
+        assert_eq!(
+            p["site-a"].args(),
+            vec!["--daemon-socket", "/tmp/site-a.sock", "--format", "senml"]
+        );
+        assert_eq!(p["site-b"].args(), vec!["--units", "imperial"]);
+
+        assert!(parse_profiles("[site-c]\nsocket = \"/tmp/c.sock\"").is_err());
+    }
+}
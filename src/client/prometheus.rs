@@ -0,0 +1,222 @@
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+
+use log::{debug, info, warn};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use dsf_core::prelude::*;
+use dsf_rpc::{DataInfo, PageBounds, ServiceIdentifier, ServiceInfo};
+
+use super::{IotClient, ListOptions, QueryOptions};
+use crate::endpoint::{EpReadings, EpValue};
+use crate::error::IotError;
+use crate::prelude::{EpData, EpDescriptor};
+
+/// Latest service data for export
+pub type ServiceData = (
+    ServiceInfo,
+    DataInfo<Vec<EpDescriptor>>,
+    Option<DataInfo<Vec<EpData>>>,
+);
+
+/// Escape prometheus label values
+fn escape(v: &str) -> String {
+    v.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Fetch name and room labels from service options
+fn service_labels(page: &DataInfo<Vec<EpDescriptor>>) -> (String, String) {
+    let (mut name, mut room) = (String::new(), String::new());
+
+    let private = match &page.private_options {
+        MaybeEncrypted::Cleartext(o) => &o[..],
+        _ => &[],
+    };
+
+    for o in page.public_options.iter().chain(private.iter()) {
+        match o {
+            Options::Name(v) => name = v.to_string(),
+            Options::Room(v) => room = v.to_string(),
+            _ => (),
+        }
+    }
+
+    (name, room)
+}
+
+/// Convert an endpoint value to a metric value, only numeric and boolean
+/// values can be exported. Durations are exported in seconds to match the
+/// units of duration endpoints (e.g. `uptime`).
+fn metric_value(v: &EpValue) -> Option<f32> {
+    match v {
+        EpValue::Bool(b) => Some(*b as u8 as f32),
+        EpValue::DurationMs(ms) => Some(*ms as f32 / 1000.0),
+        _ => v.as_f32(),
+    }
+}
+
+/// Render latest endpoint values in the prometheus text exposition format,
+/// with durations in seconds
+pub fn render_metrics(services: &[ServiceData]) -> String {
+    let mut buff = String::new();
+
+    let mut quality = String::new();
+
+    let _ = writeln!(buff, "# HELP dsf_iot_value Latest IoT endpoint value");
+    let _ = writeln!(buff, "# TYPE dsf_iot_value gauge");
+    let _ = writeln!(
+        quality,
+        "# HELP dsf_iot_quality Latest IoT endpoint value quality (0: good, 1: uncertain, 2: bad, 3: substitute)"
+    );
+    let _ = writeln!(quality, "# TYPE dsf_iot_quality gauge");
+
+    for (s, page, data) in services {
+        let (descriptors, data) = match (&page.body, data.as_ref().map(|d| &d.body)) {
+            (MaybeEncrypted::Cleartext(e), Some(MaybeEncrypted::Cleartext(d))) => (e, d),
+            _ => continue,
+        };
+
+        let (name, room) = service_labels(page);
+
+        for (i, ((e, v), d)) in EpReadings::new(descriptors, data)
+            .calibrated()
+            .zip(data.iter())
+            .enumerate()
+        {
+            let value = match metric_value(&v) {
+                Some(v) => v,
+                None => continue,
+            };
+
+            let _ = writeln!(
+                buff,
+                "dsf_iot_value{{service=\"{}\",name=\"{}\",room=\"{}\",endpoint=\"{}\",unit=\"{}\",index=\"{}\"}} {}",
+                s.id,
+                escape(&name),
+                escape(&room),
+                escape(&e.kind.to_string()),
+                escape(&e.kind.unit()),
+                i,
+                value
+            );
+
+            let _ = writeln!(
+                quality,
+                "dsf_iot_quality{{service=\"{}\",endpoint=\"{}\",index=\"{}\"}} {}",
+                s.id,
+                escape(&e.kind.to_string()),
+                i,
+                d.quality as u8
+            );
+        }
+    }
+
+    buff.push_str(&quality);
+    buff
+}
+
+/// Collect latest data for known IoT services
+async fn collect(client: &mut IotClient) -> Result<Vec<ServiceData>, IotError> {
+    let services = client
+        .list(ListOptions {
+            application_id: None,
+            kind: None,
+            bounds: PageBounds::default(),
+        })
+        .await?;
+
+    let mut data = Vec::with_capacity(services.len());
+
+    for (s, _p) in services {
+        let q = QueryOptions {
+            service: ServiceIdentifier::id(s.id.clone()),
+            bounds: PageBounds {
+                count: Some(1),
+                ..Default::default()
+            },
+        };
+
+        match client.query(q).await {
+            Ok((s, p, mut d)) => data.push((s, p, d.drain(..).next())),
+            Err(e) => warn!("Failed to fetch data for service {}: {:?}", s.id, e),
+        }
+    }
+
+    Ok(data)
+}
+
+/// Timeout for reading scrape requests
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Serve latest endpoint values via HTTP for prometheus scraping
+pub async fn serve(client: &mut IotClient, listen: SocketAddr) -> Result<(), IotError> {
+    let listener = TcpListener::bind(listen).await?;
+
+    info!("Prometheus exporter listening on {}", listen);
+
+    // Connection errors are logged and do not stop the exporter
+    loop {
+        let (mut stream, addr) = match listener.accept().await {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("Failed to accept connection: {:?}", e);
+                continue;
+            }
+        };
+
+        debug!("Scrape request from {}", addr);
+
+        // Read (and ignore) the request, all paths return metrics. Requests are
+        // handled serially so idle clients are timed out to avoid blocking scrapes.
+        let mut req = [0u8; 1024];
+        match tokio::time::timeout(REQUEST_TIMEOUT, stream.read(&mut req)).await {
+            Ok(Ok(_)) => (),
+            Ok(Err(e)) => {
+                warn!("Failed to read request from {}: {:?}", addr, e);
+                continue;
+            }
+            Err(_) => {
+                warn!("Timeout reading request from {}", addr);
+                continue;
+            }
+        }
+
+        let (status, body) = match collect(client).await {
+            Ok(d) => ("200 OK", render_metrics(&d)),
+            Err(e) => ("500 Internal Server Error", format!("{:?}\n", e)),
+        };
+
+        let resp = format!(
+            "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+
+        if let Err(e) = stream.write_all(resp.as_bytes()).await {
+            warn!("Failed to write response to {}: {:?}", addr, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_labels() {
+        assert_eq!(escape(r#"a"b\c"#), r#"a\"b\\c"#);
+        assert_eq!(escape("a\nb"), "a\\nb");
+    }
+
+    #[test]
+    fn metric_values() {
+        assert_eq!(metric_value(&EpValue::Bool(true)), Some(1.0));
+        assert_eq!(metric_value(&EpValue::Float32(21.5)), Some(21.5));
+        assert_eq!(metric_value(&EpValue::DurationMs(90_500)), Some(90.5));
+        assert_eq!(metric_value(&EpValue::from("text")), None);
+    }
+}
@@ -0,0 +1,183 @@
+//! Bulk device commissioning from a CSV manifest.
+//!
+//! Manifests contain one device per line in the form `NAME,ROOM,ENDPOINTS`,
+//! with `;` separated endpoint descriptors (as accepted by `create --endpoints`)
+//! and an optional `name,room,endpoints` header. Empty lines and lines
+//! starting with `#` are ignored.
+
+use core::fmt::Display;
+
+use dsf_core::prelude::*;
+
+use super::{CreateOptions, Identity};
+use crate::endpoint::{parse_endpoint_descriptor, EpDescriptor};
+use crate::error::IotError;
+
+/// Manifest device entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestEntry {
+    /// Device (service) name
+    pub name: String,
+    /// Device room
+    pub room: Option<String>,
+    /// Device endpoints
+    pub endpoints: Vec<EpDescriptor>,
+}
+
+impl ManifestEntry {
+    /// Build service creation options for this device
+    pub fn create_options(&self, public: bool) -> CreateOptions {
+        let mut meta = vec![("name".to_string(), self.name.clone())];
+        if let Some(r) = &self.room {
+            meta.push(("room".to_string(), r.clone()));
+        }
+
+        CreateOptions {
+            endpoints: self.endpoints.clone(),
+            meta,
+            public,
+            ..Default::default()
+        }
+    }
+}
+
+/// Parse a device manifest
+pub fn parse_manifest(src: &str) -> Result<Vec<ManifestEntry>, IotError> {
+    let mut entries: Vec<ManifestEntry> = vec![];
+
+    for (i, l) in src.lines().enumerate() {
+        let l = l.trim();
+        let line = i + 1;
+
+        if l.is_empty() || l.starts_with('#') || (i == 0 && l.starts_with("name,")) {
+            continue;
+        }
+
+        let fields: Vec<_> = l.split(',').map(|f| f.trim()).collect();
+        let (name, room, endpoints) = match &fields[..] {
+            [n, r, e] if !n.is_empty() => (*n, *r, *e),
+            _ => return Err(IotError::InvalidManifest { line }),
+        };
+
+        // Names are used for key file names so must not contain path components
+        if name.contains(['/', '\\']) || name.contains("..") {
+            return Err(IotError::InvalidManifest { line });
+        }
+
+        if entries.iter().any(|e| e.name == name) {
+            return Err(IotError::InvalidManifest { line });
+        }
+
+        let endpoints = endpoints
+            .split(';')
+            .filter(|e| !e.trim().is_empty())
+            .map(|e| parse_endpoint_descriptor(e.trim()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| IotError::InvalidManifest { line })?;
+
+        if endpoints.is_empty() {
+            return Err(IotError::InvalidManifest { line });
+        }
+
+        entries.push(ManifestEntry {
+            name: name.to_string(),
+            room: (!room.is_empty()).then(|| room.to_string()),
+            endpoints,
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Commissioned device
+#[derive(Debug)]
+pub struct Provisioned {
+    pub name: String,
+    pub id: Id,
+    pub identity: Identity,
+}
+
+/// Bulk commissioning results, with devices in manifest order
+#[derive(Debug, Default)]
+pub struct ProvisionReport {
+    pub created: Vec<Provisioned>,
+    pub failures: Vec<(String, IotError)>,
+}
+
+impl ProvisionReport {
+    /// Check whether all devices were commissioned
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl Display for ProvisionReport {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "Provisioned {} of {} devices",
+            self.created.len(),
+            self.created.len() + self.failures.len()
+        )?;
+
+        for p in &self.created {
+            write!(f, "\n  + {}: {:#}", p.name, p.id)?;
+        }
+        for (name, e) in &self.failures {
+            write!(f, "\n  - {}: {}", name, e)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoint::{EpFlags, EpKind, EpRole};
+
+    #[test]
+    fn manifest_parsing() {
+        let m = "name,room,endpoints
+# hallway sensors
+sensor-1,hall,temperature;humidity
+
+light-1,,brightness:actuator:0..100
+";
+
+        let e = parse_manifest(m).unwrap();
+        assert_eq!(e.len(), 2);
+
+        assert_eq!(e[0].name, "sensor-1");
+        assert_eq!(e[0].room.as_deref(), Some("hall"));
+        assert_eq!(
+            e[0].endpoints,
+            vec![
+                EpDescriptor::new(EpKind::Temperature, EpFlags::empty()),
+                EpDescriptor::new(EpKind::Humidity, EpFlags::empty()),
+            ]
+        );
+
+        assert_eq!(e[1].room, None);
+        assert_eq!(e[1].endpoints[0].role(), EpRole::Actuator);
+
+        let o = e[1].create_options(false);
+        assert_eq!(o.meta, vec![("name".to_string(), "light-1".to_string())]);
+
+        for (m, line) in [
+            ("sensor-1,hall", 1),
+            ("sensor-1,hall,", 1),
+            ("sensor-1,hall,not-a-kind", 1),
+            ("a,,temperature\na,,humidity", 2),
+            ("../../etc/foo,,temperature", 1),
+            ("a\\b,,temperature", 1),
+            ("..,,temperature", 1),
+        ] {
+            assert!(
+                matches!(parse_manifest(m), Err(IotError::InvalidManifest { line: l }) if l == line),
+                "{}",
+                m
+            );
+        }
+    }
+}
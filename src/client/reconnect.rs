@@ -0,0 +1,220 @@
+//! Subscription streams with automatic reconnection, re-establishing the
+//! daemon connection and resubscribing if the daemon restarts.
+
+use std::time::Duration;
+
+use futures::{prelude::*, stream, stream::LocalBoxStream};
+use log::{debug, warn};
+
+use dsf_client::{Client, Config};
+use dsf_rpc as rpc;
+
+use crate::error::IotError;
+
+/// Reconnection retry configuration, using exponential backoff
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryConfig {
+    /// Maximum number of consecutive attempts (unlimited if `None`)
+    pub max_attempts: Option<usize>,
+    /// Initial backoff between attempts
+    pub initial: Duration,
+    /// Maximum backoff between attempts
+    pub max: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: None,
+            initial: Duration::from_millis(500),
+            max: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Compute the backoff prior to the provided (zero-indexed) retry attempt
+    pub fn backoff(&self, attempt: usize) -> Duration {
+        let scale = 1u32.checked_shl(attempt.min(31) as u32).unwrap_or(u32::MAX);
+
+        self.initial
+            .checked_mul(scale)
+            .map(|d| d.min(self.max))
+            .unwrap_or(self.max)
+    }
+}
+
+/// Subscription stream events
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubscriptionEvent {
+    /// Service data updated
+    Update,
+    /// Subscription re-established after a disconnection, updates may have been missed
+    Reconnected { attempts: usize },
+}
+
+struct State<C, F> {
+    connect: F,
+    label: String,
+    retry: RetryConfig,
+    conn: Option<(C, LocalBoxStream<'static, ()>)>,
+    connected: bool,
+    done: bool,
+}
+
+/// Subscribe to a service using a dedicated daemon connection, reconnecting
+/// and resubscribing if the connection is lost
+pub fn subscribe(
+    config: Config,
+    options: rpc::SubscribeOptions,
+    retry: RetryConfig,
+) -> impl Stream<Item = Result<SubscriptionEvent, IotError>> {
+    let label = format!("{:?}", options.service);
+
+    subscribe_with(
+        move || connect(config.clone(), options.clone()),
+        label,
+        retry,
+    )
+}
+
+/// Subscribe using the provided connect function, which returns a connection
+/// (held while subscribed) and its update stream
+fn subscribe_with<C, F, R>(
+    connect: F,
+    label: String,
+    retry: RetryConfig,
+) -> impl Stream<Item = Result<SubscriptionEvent, IotError>>
+where
+    F: FnMut() -> R,
+    R: Future<Output = Result<(C, LocalBoxStream<'static, ()>), IotError>>,
+{
+    let state = State {
+        connect,
+        label,
+        retry,
+        conn: None,
+        connected: false,
+        done: false,
+    };
+
+    stream::unfold(state, |mut s| async move {
+        let mut attempts = 0;
+
+        loop {
+            if s.done {
+                return None;
+            }
+
+            // Forward updates from the active subscription
+            if let Some((_c, updates)) = &mut s.conn {
+                match updates.next().await {
+                    Some(_) => return Some((Ok(SubscriptionEvent::Update), s)),
+                    None => {
+                        warn!("Subscription to {} lost, reconnecting", s.label);
+                        s.conn = None;
+                    }
+                }
+            }
+
+            // Wait before re-attempting connection
+            if attempts > 0 {
+                tokio::time::sleep(s.retry.backoff(attempts - 1)).await;
+            }
+
+            match (s.connect)().await {
+                Ok(conn) => {
+                    s.conn = Some(conn);
+
+                    // Signal reconnections (but not the initial connection)
+                    if s.connected {
+                        debug!("Resubscribed to {}", s.label);
+                        return Some((
+                            Ok(SubscriptionEvent::Reconnected {
+                                attempts: attempts + 1,
+                            }),
+                            s,
+                        ));
+                    }
+                    s.connected = true;
+                }
+                Err(e) => {
+                    attempts += 1;
+
+                    if s.retry.max_attempts.map(|m| attempts >= m).unwrap_or(false) {
+                        s.done = true;
+                        return Some((Err(e), s));
+                    }
+
+                    debug!("Connection attempt {} failed: {:?}", attempts, e);
+                }
+            }
+        }
+    })
+}
+
+/// Connect to the daemon and subscribe to the service
+async fn connect(
+    config: Config,
+    options: rpc::SubscribeOptions,
+) -> Result<(Client, LocalBoxStream<'static, ()>), IotError> {
+    let mut client = Client::new(config).await?;
+    let updates = client.subscribe(options).await?;
+
+    Ok((client, updates.map(|_| ()).boxed_local()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_backoff() {
+        let r = RetryConfig {
+            max_attempts: None,
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(1),
+        };
+
+        assert_eq!(r.backoff(0), Duration::from_millis(100));
+        assert_eq!(r.backoff(2), Duration::from_millis(400));
+        assert_eq!(r.backoff(4), Duration::from_secs(1));
+        assert_eq!(r.backoff(100), Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn resubscribe_after_loss() {
+        let retry = RetryConfig {
+            max_attempts: Some(2),
+            initial: Duration::from_millis(1),
+            max: Duration::from_millis(10),
+        };
+
+        // Connect, lose the stream after two updates, fail once then reconnect,
+        // lose the stream again and fail until retries are exhausted
+        let mut n = 0;
+        let connect = move || {
+            n += 1;
+            let r = match n {
+                1 => Ok(((), stream::iter([(), ()]).boxed_local())),
+                3 => Ok(((), stream::iter([()]).boxed_local())),
+                _ => Err(IotError::NoEndpoint),
+            };
+            future::ready(r)
+        };
+
+        let events: Vec<_> = subscribe_with(connect, "test".to_string(), retry)
+            .collect()
+            .await;
+
+        assert_eq!(events.len(), 5);
+        assert_eq!(events[0].as_ref().unwrap(), &SubscriptionEvent::Update);
+        assert_eq!(events[1].as_ref().unwrap(), &SubscriptionEvent::Update);
+        assert_eq!(
+            events[2].as_ref().unwrap(),
+            &SubscriptionEvent::Reconnected { attempts: 2 }
+        );
+        assert_eq!(events[3].as_ref().unwrap(), &SubscriptionEvent::Update);
+        assert!(events[4].is_err());
+    }
+}
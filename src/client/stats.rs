@@ -0,0 +1,112 @@
+//! Per-endpoint summary statistics over queried data objects.
+//!
+//! Statistics are computed over numeric values in endpoint base units,
+//! values with [`EpQuality::Bad`] are excluded.
+
+use core::fmt::Display;
+
+use crate::endpoint::{EpData, EpQuality};
+
+/// Summary statistics for an endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct EpStats {
+    /// Number of numeric samples
+    pub count: usize,
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+    /// Population standard deviation
+    pub stddev: f32,
+    // Sum of squared differences from the mean (Welford's algorithm)
+    m2: f32,
+}
+
+impl EpStats {
+    /// Update statistics with a new sample
+    pub fn update(&mut self, v: f32) {
+        if self.count == 0 {
+            self.min = v;
+            self.max = v;
+        } else {
+            self.min = self.min.min(v);
+            self.max = self.max.max(v);
+        }
+
+        self.count += 1;
+        let delta = v - self.mean;
+        self.mean += delta / self.count as f32;
+        self.m2 += delta * (v - self.mean);
+        self.stddev = (self.m2 / self.count as f32).sqrt();
+    }
+}
+
+impl Display for EpStats {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.count == 0 {
+            return write!(f, "no numeric samples");
+        }
+
+        write!(
+            f,
+            "n: {} min: {:.3} max: {:.3} mean: {:.3} stddev: {:.3}",
+            self.count, self.min, self.max, self.mean, self.stddev
+        )
+    }
+}
+
+/// Compute statistics for `n` endpoints over a set of data objects
+pub fn endpoint_stats<'a>(
+    n: usize,
+    objects: impl IntoIterator<Item = &'a [EpData]>,
+) -> Vec<EpStats> {
+    let mut stats = vec![EpStats::default(); n];
+
+    for o in objects {
+        for (s, d) in stats.iter_mut().zip(o) {
+            if d.quality == EpQuality::Bad {
+                continue;
+            }
+            if let Some(v) = d.value.as_f32() {
+                s.update(v);
+            }
+        }
+    }
+
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoint::EpValue;
+
+    #[test]
+    fn endpoint_statistics() {
+        let objects = [
+            vec![
+                EpData::new(EpValue::Float32(2.0)),
+                EpData::new(EpValue::Bool(true)),
+            ],
+            vec![
+                EpData::new(EpValue::Float32(4.0)),
+                EpData::new(EpValue::Int32(1)),
+            ],
+            vec![EpData::new(EpValue::Float32(100.0)).with_quality(EpQuality::Bad)],
+            vec![EpData::new(EpValue::Float32(6.0))],
+        ];
+
+        let s = endpoint_stats(3, objects.iter().map(|o| &o[..]));
+        assert_eq!(s.len(), 3);
+
+        assert_eq!(s[0].count, 3);
+        assert_eq!((s[0].min, s[0].max, s[0].mean), (2.0, 6.0, 4.0));
+        assert!((s[0].stddev - 1.633).abs() < 1e-3);
+
+        assert_eq!(s[1].count, 1);
+        assert_eq!(s[1].mean, 1.0);
+        assert_eq!(s[1].stddev, 0.0);
+
+        assert_eq!(s[2].count, 0);
+        assert_eq!(s[2].to_string(), "no numeric samples");
+    }
+}
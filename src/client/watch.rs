@@ -0,0 +1,91 @@
+use std::process::ExitStatus;
+
+use log::debug;
+use tokio::process::Command;
+
+use crate::endpoint::EpValue;
+use crate::error::IotError;
+
+/// Filter for matching endpoint values
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ValueFilter {
+    /// Match numeric values above this threshold
+    pub above: Option<f32>,
+    /// Match numeric values below this threshold
+    pub below: Option<f32>,
+    /// Match values equal to this value
+    pub equals: Option<EpValue>,
+}
+
+impl ValueFilter {
+    /// Check whether a value matches all configured filters
+    pub fn matches(&self, v: &EpValue) -> bool {
+        if let Some(e) = &self.equals {
+            if e != v {
+                return false;
+            }
+        }
+
+        if self.above.is_none() && self.below.is_none() {
+            return true;
+        }
+
+        // Thresholds only apply to numeric values
+        let n = match v.as_f32() {
+            Some(n) => n,
+            None => return false,
+        };
+
+        self.above.map(|a| n > a).unwrap_or(true) && self.below.map(|b| n < b).unwrap_or(true)
+    }
+}
+
+/// Run an action command with the provided environment variables,
+/// waiting for the child process to exit
+pub async fn run_action(cmd: &[String], env: &[(&str, String)]) -> Result<ExitStatus, IotError> {
+    let (program, args) = match cmd.split_first() {
+        Some(v) => v,
+        None => return Err(IotError::NoCommand),
+    };
+
+    debug!("Executing action: {:?} (env: {:?})", cmd, env);
+
+    let status = Command::new(program)
+        .args(args)
+        .envs(env.iter().map(|(k, v)| (k, v)))
+        .status()
+        .await?;
+
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn value_filters() {
+        let f = ValueFilter {
+            above: Some(30.0),
+            ..Default::default()
+        };
+        assert!(f.matches(&EpValue::Float32(30.5)));
+        assert!(!f.matches(&EpValue::Float32(29.0)));
+        assert!(!f.matches(&EpValue::Bool(true)));
+
+        let f = ValueFilter {
+            above: Some(10.0),
+            below: Some(20.0),
+            ..Default::default()
+        };
+        assert!(f.matches(&EpValue::Int32(15)));
+        assert!(!f.matches(&EpValue::Int32(25)));
+
+        let f = ValueFilter {
+            equals: Some(EpValue::Bool(true)),
+            ..Default::default()
+        };
+        assert!(f.matches(&EpValue::Bool(true)));
+        assert!(!f.matches(&EpValue::Bool(false)));
+    }
+}
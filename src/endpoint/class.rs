@@ -0,0 +1,134 @@
+//! Standard device classes, providing predefined endpoint descriptor bundles
+//! so devices of the same class share endpoint ordering and kinds across
+//! vendors, enabling generic controllers.
+//!
+//! Class endpoints precede any additional (vendor specific) endpoints.
+
+use core::str::FromStr;
+
+use heapless::Vec;
+
+use super::desc::*;
+use super::kinds::*;
+use super::MAX_ENDPOINTS;
+
+use crate::error::IotError;
+
+/// Metadata key used to publish the device class of a service
+pub const CLASS_META_KEY: &str = "class";
+
+/// Standard device classes
+#[derive(
+    Debug, Copy, Clone, PartialEq, strum::EnumString, strum::IntoStaticStr, strum::EnumIter,
+)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+#[strum(serialize_all = "kebab-case", ascii_case_insensitive)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DeviceClass {
+    /// Temperature, humidity and pressure sensor
+    EnvironmentSensor,
+    /// Dimmable colour light (state, brightness, colour)
+    SmartLight,
+    /// Switched outlet with power monitoring (state, power, energy)
+    SmartPlug,
+    /// Door / window contact sensor (state, open events, battery)
+    DoorSensor,
+}
+
+impl DeviceClass {
+    /// Build endpoint descriptors for the device class, in standard order
+    pub fn descriptors(&self) -> Vec<EpDescriptor, MAX_ENDPOINTS> {
+        let percent = EpConstraints {
+            min: Some(0.0),
+            max: Some(100.0),
+            step: Some(1.0),
+        };
+
+        let d = match self {
+            Self::EnvironmentSensor => Vec::from_slice(&[
+                EpDescriptor::new(EpKind::Temperature, EpFlags::R),
+                EpDescriptor::new(EpKind::Humidity, EpFlags::R),
+                EpDescriptor::new(EpKind::Pressure, EpFlags::R),
+            ]),
+            Self::SmartLight => Vec::from_slice(&[
+                EpDescriptor::new(EpKind::State, EpFlags::RW),
+                EpDescriptor::new(EpKind::Brightness, EpFlags::RW).with_constraints(percent),
+                EpDescriptor::new(EpKind::Colour, EpFlags::RW),
+            ]),
+            Self::SmartPlug => Vec::from_slice(&[
+                EpDescriptor::new(EpKind::State, EpFlags::RW),
+                EpDescriptor::new(EpKind::Power, EpFlags::R),
+                EpDescriptor::new(EpKind::Energy, EpFlags::R),
+            ]),
+            Self::DoorSensor => Vec::from_slice(&[
+                EpDescriptor::new(EpKind::State, EpFlags::R),
+                EpDescriptor::new(EpKind::Event, EpFlags::R).with_role(EpRole::Event),
+                EpDescriptor::new(EpKind::Battery, EpFlags::R),
+            ]),
+        };
+
+        // Class bundles are within the default endpoint limit
+        d.unwrap()
+    }
+
+    /// Check whether the provided descriptors match this class,
+    /// with additional endpoints permitted following the class endpoints
+    pub fn matches(&self, descriptors: &[EpDescriptor]) -> bool {
+        let class = self.descriptors();
+
+        descriptors.len() >= class.len()
+            && class
+                .iter()
+                .zip(descriptors)
+                .all(|(c, d)| c.kind == d.kind && c.flags == d.flags)
+    }
+}
+
+impl core::fmt::Display for DeviceClass {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.pad(<&'static str>::from(self))
+    }
+}
+
+/// Parse a device class from a string
+pub fn parse_device_class(src: &str) -> Result<DeviceClass, IotError> {
+    DeviceClass::from_str(src).map_err(|_| IotError::UnrecognisedClass)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn device_classes() {
+        assert_eq!(
+            parse_device_class("smart-light").unwrap(),
+            DeviceClass::SmartLight
+        );
+        assert_eq!(
+            parse_device_class("Door-Sensor").unwrap(),
+            DeviceClass::DoorSensor
+        );
+        assert!(parse_device_class("toaster").is_err());
+
+        for c in DeviceClass::iter() {
+            // Names round-trip
+            assert_eq!(parse_device_class(&c.to_string()).unwrap(), c);
+
+            // Class descriptors match, including with additional endpoints
+            let mut d = c.descriptors();
+            assert!(!d.is_empty());
+            assert!(c.matches(&d));
+
+            d.push(EpDescriptor::new(EpKind::Rssi, EpFlags::R)).unwrap();
+            assert!(c.matches(&d));
+            assert!(!c.matches(&d[1..]));
+        }
+
+        let d = DeviceClass::SmartLight.descriptors();
+        assert_eq!(d[1].role(), EpRole::Actuator);
+        assert_eq!(d[1].constraints.max, Some(100.0));
+    }
+}
@@ -0,0 +1,148 @@
+//! Endpoint unit conversion helpers, converting values from the base units in
+//! [`ENDPOINT_KINDS`] to alternate display or export units.
+
+#[cfg(feature = "alloc")]
+use alloc::string::{String, ToString};
+
+use super::kinds::*;
+use super::value::*;
+use crate::prelude::IotError;
+
+/// Linear unit conversions as `(from, to, scale, offset)` where `to = from * scale + offset`
+pub const UNIT_CONVERSIONS: &[(&str, &str, f32, f32)] = &[
+    ("°C", "°F", 1.8, 32.0),
+    ("°C", "K", 1.0, 273.15),
+    ("kPa", "hPa", 10.0, 0.0),
+    ("kPa", "Pa", 1000.0, 0.0),
+    ("kPa", "inHg", 0.295_300, 0.0),
+    ("kPa", "mmHg", 7.500_617, 0.0),
+    ("kPa", "psi", 0.145_038, 0.0),
+    ("lux", "fc", 0.092_903, 0.0),
+];
+
+/// Convert a value between units, returning None for unsupported conversions
+pub fn convert_unit(v: f32, from: &str, to: &str) -> Option<f32> {
+    if from == to {
+        return Some(v);
+    }
+
+    for (f, t, scale, offset) in UNIT_CONVERSIONS {
+        if *f == from && *t == to {
+            return Some(v * scale + offset);
+        }
+        if *f == to && *t == from {
+            return Some((v - offset) / scale);
+        }
+    }
+
+    None
+}
+
+impl EpValue {
+    /// Convert a value from the base unit of the provided endpoint kind to the target unit
+    pub fn convert(&self, kind: &EpKind, target: &str) -> Result<EpValue, IotError> {
+        let v = match self {
+            EpValue::Float32(v) => *v,
+            EpValue::Int32(v) => *v as f32,
+            _ => return Err(IotError::UnsupportedConversion),
+        };
+
+        // Preserve value types where no conversion is required
+        let unit = kind.unit();
+        if unit == target {
+            return Ok(self.clone());
+        }
+
+        match convert_unit(v, &unit, target) {
+            Some(v) => Ok(EpValue::Float32(v)),
+            None => Err(IotError::UnsupportedConversion),
+        }
+    }
+}
+
+/// Unit system for display and export
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum UnitSystem {
+    /// Endpoint base units (as defined in [`ENDPOINT_KINDS`])
+    Metric,
+    /// Imperial units where applicable
+    Imperial,
+}
+
+impl Default for UnitSystem {
+    fn default() -> Self {
+        Self::Metric
+    }
+}
+
+impl UnitSystem {
+    /// Fetch the preferred unit for an endpoint kind in this unit system
+    pub fn unit(&self, kind: &EpKind) -> String {
+        match (self, kind) {
+            (Self::Imperial, EpKind::Temperature) => "°F".to_string(),
+            (Self::Imperial, EpKind::Pressure) => "inHg".to_string(),
+            _ => kind.unit(),
+        }
+    }
+
+    /// Convert an endpoint value to this unit system, returning the value and unit.
+    /// Values that cannot be converted are returned in the endpoint base unit.
+    pub fn apply(&self, kind: &EpKind, value: &EpValue) -> (EpValue, String) {
+        let unit = self.unit(kind);
+
+        match value.convert(kind, &unit) {
+            Ok(v) => (v, unit),
+            Err(_) => (value.clone(), kind.unit()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(a: f32, b: f32) -> bool {
+        (a - b).abs() < 0.01
+    }
+
+    #[test]
+    fn convert_units() {
+        let tests = &[
+            (EpKind::Temperature, 20.0, "°F", 68.0),
+            (EpKind::Temperature, -40.0, "°F", -40.0),
+            (EpKind::Temperature, 0.0, "K", 273.15),
+            (EpKind::Pressure, 101.325, "hPa", 1013.25),
+            (EpKind::Pressure, 101.325, "inHg", 29.92),
+            (EpKind::Humidity, 55.0, "%RH", 55.0),
+        ];
+
+        for (kind, v, unit, expected) in tests {
+            let c = EpValue::Float32(*v).convert(kind, unit).unwrap();
+            match c {
+                EpValue::Float32(c) => assert!(approx(c, *expected), "{} {} -> {}", v, unit, c),
+                _ => panic!("unexpected value type"),
+            }
+        }
+    }
+
+    #[test]
+    fn convert_units_reverse() {
+        assert!(approx(convert_unit(68.0, "°F", "°C").unwrap(), 20.0));
+        assert!(approx(
+            convert_unit(1013.25, "hPa", "kPa").unwrap(),
+            101.325
+        ));
+    }
+
+    #[test]
+    fn convert_unsupported() {
+        assert!(EpValue::Float32(1.0).convert(&EpKind::Co2, "°F").is_err());
+        assert!(EpValue::Bool(true).convert(&EpKind::State, "bool").is_err());
+
+        let (v, u) = UnitSystem::Imperial.apply(&EpKind::Co2, &EpValue::Int32(400));
+        assert_eq!(v, EpValue::Int32(400));
+        assert_eq!(u, "ppm");
+    }
+}
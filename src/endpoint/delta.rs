@@ -0,0 +1,121 @@
+//! Differential data objects, carrying only endpoints changed since the
+//! previous object to reduce payload sizes for services with many mostly
+//! static endpoints.
+//!
+//! Values in differential objects carry the index of the endpoint they apply
+//! to, subscribers reconstruct full state by applying these to the values of
+//! the last full data object.
+
+use super::desc::*;
+use super::IotData;
+
+use crate::error::IotError;
+
+/// Check whether data values form a differential object
+pub fn is_delta(data: &[EpData]) -> bool {
+    data.iter().any(|d| d.index.is_some())
+}
+
+/// Build a differential data object containing the values in `next` that
+/// differ from `prev`.
+///
+/// Where no values have changed the first value is included, as empty
+/// objects cannot be distinguished from full objects. Where the number of
+/// values differs (e.g. when diagnostics are appended) a full object is
+/// returned, as the delta could not be applied to the previous object.
+pub fn delta<const N: usize>(prev: &[EpData], next: &[EpData]) -> Result<IotData<N>, IotError> {
+    if prev.len() != next.len() {
+        return IotData::new(next);
+    }
+
+    let mut d = IotData::<N>::new(&[])?;
+
+    for (i, v) in next.iter().enumerate() {
+        if prev.get(i) == Some(v) {
+            continue;
+        }
+
+        let v = v.clone().with_index(i as u16);
+        if d.data.push(v).is_err() {
+            return Err(IotError::TooManyEndpoints {
+                count: next.len(),
+                max: N,
+            });
+        }
+    }
+
+    if d.data.is_empty() {
+        if let Some(v) = next.first() {
+            let _ = d.data.push(v.clone().with_index(0));
+        }
+    }
+
+    Ok(d)
+}
+
+/// Apply a differential data object to the values of the last full object
+pub fn apply_delta(state: &mut [EpData], delta: &[EpData]) -> Result<(), IotError> {
+    for d in delta {
+        let s = match d.index.and_then(|i| state.get_mut(i as usize)) {
+            Some(s) => s,
+            None => return Err(IotError::NoEndpoint),
+        };
+
+        *s = EpData {
+            index: None,
+            ..d.clone()
+        };
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoint::EpValue;
+
+    #[test]
+    fn delta_objects() {
+        let prev = [
+            EpData::new(EpValue::Float32(21.0)),
+            EpData::new(EpValue::Bool(false)),
+            EpData::new(EpValue::Int32(3)),
+        ];
+        let next = [
+            EpData::new(EpValue::Float32(21.5)),
+            EpData::new(EpValue::Bool(false)),
+            EpData::new(EpValue::Int32(3)).with_quality(EpQuality::Uncertain),
+        ];
+
+        let d = delta::<8>(&prev, &next).unwrap();
+        assert!(is_delta(&d.data));
+        assert_eq!(
+            &d.data[..],
+            &[
+                EpData::new(EpValue::Float32(21.5)).with_index(0),
+                EpData::new(EpValue::Int32(3))
+                    .with_quality(EpQuality::Uncertain)
+                    .with_index(2),
+            ]
+        );
+
+        let mut state = prev.clone();
+        apply_delta(&mut state, &d.data).unwrap();
+        assert_eq!(state, next);
+        assert!(!is_delta(&state));
+
+        // Unchanged values produce a delta containing the first value
+        let d = delta::<8>(&next, &next).unwrap();
+        assert_eq!(&d.data[..], &[next[0].clone().with_index(0)]);
+
+        // Objects with differing endpoints are sent in full
+        let d = delta::<8>(&next[..2], &next).unwrap();
+        assert!(!is_delta(&d.data));
+        assert_eq!(&d.data[..], &next[..]);
+
+        // Deltas must reference existing endpoints
+        let d = [EpData::new(EpValue::Bool(true)).with_index(3)];
+        assert!(apply_delta(&mut state, &d).is_err());
+    }
+}
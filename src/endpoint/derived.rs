@@ -0,0 +1,108 @@
+//! Derived endpoints, computing rates of change from successive values of a
+//! source endpoint (e.g. l/min from a cumulative counter).
+//!
+//! Derived endpoints are published with their own endpoint kind, which
+//! provides the rate unit (e.g. a registered `flow_rate` kind in l/min).
+
+use super::kinds::*;
+use super::value::*;
+
+/// Time base for rate calculations
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum RatePer {
+    Second,
+    Minute,
+    Hour,
+}
+
+impl Default for RatePer {
+    fn default() -> Self {
+        Self::Minute
+    }
+}
+
+impl RatePer {
+    /// Length of the time base in milliseconds
+    pub fn millis(&self) -> u64 {
+        match self {
+            Self::Second => 1_000,
+            Self::Minute => 60_000,
+            Self::Hour => 3_600_000,
+        }
+    }
+}
+
+/// Rate of change tracker for a source endpoint
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EpRate {
+    /// Source endpoint kind
+    pub source: EpKind,
+    /// Rate time base
+    pub per: RatePer,
+    last: Option<(EpValue, u64)>,
+}
+
+impl EpRate {
+    /// Create a new rate tracker for the provided source endpoint kind
+    pub fn new(source: EpKind, per: RatePer) -> Self {
+        Self {
+            source,
+            per,
+            last: None,
+        }
+    }
+
+    /// Update with a source value at `time_ms`, returning the rate since the previous value.
+    ///
+    /// Returns `None` for the first value, non-numeric or mismatched values,
+    /// and values that do not advance in time.
+    pub fn update(&mut self, value: &EpValue, time_ms: u64) -> Option<f32> {
+        let prev = self.last.replace((value.clone(), time_ms));
+
+        let (prev, prev_ms) = prev?;
+        if time_ms <= prev_ms {
+            return None;
+        }
+
+        let delta = value.delta(&prev)?.as_f32()?;
+        let elapsed = (time_ms - prev_ms) as f32 / self.per.millis() as f32;
+
+        Some(delta / elapsed)
+    }
+
+    /// Reset rate tracking, discarding the previous value
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counter_rate() {
+        let mut r = EpRate::new(EpKind::Unknown(0x1000), RatePer::Minute);
+
+        assert_eq!(r.update(&EpValue::Counter64(100), 0), None);
+        assert_eq!(r.update(&EpValue::Counter64(130), 30_000), Some(60.0));
+
+        // Counter rollover yields the wrapped delta
+        r.reset();
+        assert_eq!(r.update(&EpValue::Counter64(u64::MAX - 4), 0), None);
+        assert_eq!(r.update(&EpValue::Counter64(5), 60_000), Some(10.0));
+    }
+
+    #[test]
+    fn rate_invalid() {
+        let mut r = EpRate::new(EpKind::Temperature, RatePer::Hour);
+        r.update(&EpValue::Float32(20.0), 1_000);
+        assert_eq!(r.update(&EpValue::Float32(21.0), 1_000), None);
+        assert_eq!(r.update(&EpValue::Bool(true), 2_000), None);
+        assert_eq!(r.update(&EpValue::Bool(true), 3_000), None);
+    }
+}
@@ -25,6 +25,12 @@ pub mod iot_option_kinds {
     pub const VALUE_INT: u16 = 0x0005 | (1 << 15);
     pub const VALUE_STRING: u16 = 0x0006 | (1 << 15);
     pub const VALUE_RAW: u16 = 0x0007 | (1 << 15);
+    pub const VALUE_COUNTER: u16 = 0x0008 | (1 << 15);
+    pub const VALUE_DURATION: u16 = 0x0009 | (1 << 15);
+    pub const VALUE_PERCENT: u16 = 0x000a | (1 << 15);
+    pub const DATA_QUALITY: u16 = 0x000b | (1 << 15);
+    pub const DATA_INDEX: u16 = 0x000c | (1 << 15);
+    pub const VALUE_EVENT: u16 = 0x000d | (1 << 15);
 
     pub const ENDPOINT_DESCRIPTOR_LEN: usize = 4;
 }
@@ -43,6 +49,200 @@ bitflags::bitflags! {
     }
 }
 
+impl core::fmt::Display for EpFlags {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let r = if self.contains(EpFlags::R) { 'R' } else { '-' };
+        let w = if self.contains(EpFlags::W) { 'W' } else { '-' };
+        write!(f, "{}{}", r, w)
+    }
+}
+
+/// Endpoint roles, describing how endpoints are presented and controlled
+#[derive(Debug, Copy, Clone, PartialEq, strum::EnumString, strum::IntoStaticStr)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[strum(serialize_all = "snake_case")]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum EpRole {
+    /// Measured value
+    Sensor = 1,
+    /// Controllable output (e.g. relay, dimmer)
+    Actuator = 2,
+    /// Device configuration parameter (e.g. setpoint)
+    Config = 3,
+    /// Discrete event (e.g. button press)
+    Event = 4,
+}
+
+impl EpRole {
+    fn from_u8(v: u8) -> Option<Self> {
+        match v {
+            1 => Some(Self::Sensor),
+            2 => Some(Self::Actuator),
+            3 => Some(Self::Config),
+            4 => Some(Self::Event),
+            _ => None,
+        }
+    }
+}
+
+impl core::fmt::Display for EpRole {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let s: &'static str = self.into();
+        write!(f, "{}", s)
+    }
+}
+
+/// Value constraints for numeric endpoints
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EpConstraints {
+    /// Minimum value
+    pub min: Option<f32>,
+    /// Maximum value
+    pub max: Option<f32>,
+    /// Value step, relative to the minimum (or zero)
+    pub step: Option<f32>,
+}
+
+impl EpConstraints {
+    /// Check whether any constraints are set
+    pub fn is_empty(&self) -> bool {
+        self.min.is_none() && self.max.is_none() && self.step.is_none()
+    }
+
+    /// Check a value against constraints, returning [`IotError::OutOfRange`]
+    /// for violations and [`IotError::MismatchedType`] for non-numeric values
+    pub fn check(&self, value: &EpValue) -> Result<(), IotError> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        let v = value.as_f32().ok_or(IotError::MismatchedType)?;
+
+        if self.min.map(|m| v < m).unwrap_or(false) || self.max.map(|m| v > m).unwrap_or(false) {
+            return Err(IotError::OutOfRange);
+        }
+
+        if let Some(step) = self.step.filter(|s| *s > 0.0) {
+            let n = (v - self.min.unwrap_or(0.0)) / step;
+            let frac = (n - n as i64 as f32).abs();
+
+            if frac > STEP_EPSILON && (1.0 - frac) > STEP_EPSILON {
+                return Err(IotError::OutOfRange);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Constraint presence mask, as encoded in descriptor metadata
+    fn mask(&self) -> u8 {
+        self.min.map(|_| 1).unwrap_or(0)
+            | self.max.map(|_| 2).unwrap_or(0)
+            | self.step.map(|_| 4).unwrap_or(0)
+    }
+}
+
+/// Tolerance for step constraint checks, in steps
+const STEP_EPSILON: f32 = 1e-3;
+
+impl core::fmt::Display for EpConstraints {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if let Some(v) = self.min {
+            write!(f, "{}", v)?;
+        }
+        write!(f, "..")?;
+        if let Some(v) = self.max {
+            write!(f, "{}", v)?;
+        }
+        if let Some(v) = self.step {
+            write!(f, "/{}", v)?;
+        }
+        Ok(())
+    }
+}
+
+/// Endpoint calibration, correcting raw values using a polynomial
+/// (`offset + scale·x + quadratic·x² + cubic·x³`)
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EpCalibration {
+    pub offset: f32,
+    pub scale: f32,
+    pub quadratic: f32,
+    pub cubic: f32,
+}
+
+impl Default for EpCalibration {
+    fn default() -> Self {
+        Self {
+            offset: 0.0,
+            scale: 1.0,
+            quadratic: 0.0,
+            cubic: 0.0,
+        }
+    }
+}
+
+impl EpCalibration {
+    /// Create a linear calibration
+    pub fn linear(offset: f32, scale: f32) -> Self {
+        Self {
+            offset,
+            scale,
+            ..Default::default()
+        }
+    }
+
+    /// Check whether this calibration leaves values unchanged
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Apply calibration to a raw value
+    pub fn apply_f32(&self, x: f32) -> f32 {
+        self.offset + x * (self.scale + x * (self.quadratic + x * self.cubic))
+    }
+
+    /// Apply calibration to a raw endpoint value, numeric values other than
+    /// durations are converted to floats, other values are unchanged
+    pub fn apply(&self, v: &EpValue) -> EpValue {
+        if self.is_identity() {
+            return v.clone();
+        }
+
+        match v {
+            EpValue::Percent(x) => EpValue::Percent(self.apply_f32(*x)),
+            EpValue::DurationMs(_) | EpValue::Event { .. } => v.clone(),
+            _ => match v.as_f32() {
+                Some(x) => EpValue::Float32(self.apply_f32(x)),
+                None => v.clone(),
+            },
+        }
+    }
+
+    fn coefficients(&self) -> [f32; 4] {
+        [self.offset, self.scale, self.quadratic, self.cubic]
+    }
+}
+
+impl core::fmt::Display for EpCalibration {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}·x{:+}", self.scale, self.offset)?;
+        if self.quadratic != 0.0 || self.cubic != 0.0 {
+            write!(f, " {:+}·x² {:+}·x³", self.quadratic, self.cubic)?;
+        }
+        Ok(())
+    }
+}
+
+/// Descriptor metadata mask bit indicating calibration coefficients are present
+const CALIBRATION_MASK: u8 = 1 << 3;
+
 /// An endpoint descriptor defines the kind of an endpoint
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
@@ -54,17 +254,97 @@ pub struct EpDescriptor {
 
     /// Endpoint flags
     pub flags: EpFlags,
+
+    /// Endpoint role, inferred from flags if not set
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub role: Option<EpRole>,
+
+    /// Endpoint value constraints
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub constraints: EpConstraints,
+
+    /// Calibration to be applied to published (raw) values
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub calibration: EpCalibration,
 }
 
 impl EpDescriptor {
     pub fn new(kind: EpKind, flags: EpFlags) -> Self {
-        Self { kind, flags }
+        Self {
+            kind,
+            flags,
+            role: None,
+            constraints: EpConstraints::default(),
+            calibration: EpCalibration::default(),
+        }
+    }
+
+    /// Set the endpoint role
+    pub fn with_role(mut self, role: EpRole) -> Self {
+        self.role = Some(role);
+        self
+    }
+
+    /// Set endpoint value constraints
+    pub fn with_constraints(mut self, constraints: EpConstraints) -> Self {
+        self.constraints = constraints;
+        self
+    }
+
+    /// Set endpoint calibration
+    pub fn with_calibration(mut self, calibration: EpCalibration) -> Self {
+        self.calibration = calibration;
+        self
+    }
+
+    /// Fetch the endpoint role, writable endpoints default to actuators
+    pub fn role(&self) -> EpRole {
+        match (self.role, self.flags.contains(EpFlags::W)) {
+            (Some(r), _) => r,
+            (None, true) => EpRole::Actuator,
+            (None, false) => EpRole::Sensor,
+        }
+    }
+
+    /// Validate a control value prior to writing this endpoint
+    pub fn validate(&self, value: &EpValue) -> Result<(), IotError> {
+        if !self.flags.contains(EpFlags::W) {
+            return Err(IotError::ReadOnly);
+        }
+
+        self.constraints.check(value)
+    }
+
+    /// Descriptor metadata mask (constraints and calibration presence)
+    fn meta_mask(&self) -> u8 {
+        match self.calibration.is_identity() {
+            true => self.constraints.mask(),
+            false => self.constraints.mask() | CALIBRATION_MASK,
+        }
+    }
+
+    /// Length of encoded descriptor metadata (role, constraints and calibration)
+    fn meta_len(&self) -> usize {
+        let calibration_len = match self.calibration.is_identity() {
+            true => 0,
+            false => 16,
+        };
+
+        match (self.role, self.meta_mask()) {
+            (None, 0) => 0,
+            _ => 2 + 4 * self.constraints.mask().count_ones() as usize + calibration_len,
+        }
     }
 }
 
 impl core::fmt::Display for EpDescriptor {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        write!(f, "{:16} in {:4}\r\n", self.kind, self.kind.unit())
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        let unit = self.kind.unit();
+        #[cfg(not(any(feature = "std", feature = "alloc")))]
+        let unit = self.kind.base_unit();
+
+        write!(f, "{:16} in {:4}\r\n", self.kind, unit)
     }
 }
 
@@ -72,24 +352,42 @@ impl encdec::Encode for EpDescriptor {
     type Error = Error;
 
     fn encode_len(&self) -> Result<usize, Self::Error> {
-        Ok(8)
+        Ok(4 + iot_option_kinds::ENDPOINT_DESCRIPTOR_LEN + self.meta_len())
     }
 
     fn encode(&self, data: &mut [u8]) -> Result<usize, Error> {
+        let len = iot_option_kinds::ENDPOINT_DESCRIPTOR_LEN + self.meta_len();
+
         // Write option header (option kind and length)
         LittleEndian::write_u16(&mut data[0..], iot_option_kinds::ENDPOINT_DESCRIPTOR);
-        LittleEndian::write_u16(
-            &mut data[2..],
-            iot_option_kinds::ENDPOINT_DESCRIPTOR_LEN as u16,
-        );
+        LittleEndian::write_u16(&mut data[2..], len as u16);
 
         // Write option data (endpoint kind, reserved flags)
         LittleEndian::write_u16(&mut data[4..], u16::from(&self.kind));
         LittleEndian::write_u16(&mut data[6..], self.flags.bits());
 
-        // TODO: write metadata
+        // Write metadata (role, mask, present constraints, then calibration coefficients)
+        if self.meta_len() > 0 {
+            let c = &self.constraints;
 
-        Ok(8)
+            data[8] = self.role.map(|r| r as u8).unwrap_or(0);
+            data[9] = self.meta_mask();
+
+            let mut n = 10;
+            for v in [c.min, c.max, c.step].iter().filter_map(|v| *v) {
+                LittleEndian::write_f32(&mut data[n..], v);
+                n += 4;
+            }
+
+            if !self.calibration.is_identity() {
+                for v in self.calibration.coefficients() {
+                    LittleEndian::write_f32(&mut data[n..], v);
+                    n += 4;
+                }
+            }
+        }
+
+        Ok(4 + len)
     }
 }
 
@@ -100,6 +398,11 @@ impl encdec::DecodeOwned for EpDescriptor {
     fn decode_owned(buff: &[u8]) -> Result<(Self::Output, usize), Self::Error> {
         trace!("Parsing: {:x?}", buff);
 
+        // Check option header is available
+        if buff.len() < 4 {
+            return Err(Error::InvalidOption);
+        }
+
         // Read option header (kind and length)
         let option_kind = LittleEndian::read_u16(buff);
 
@@ -107,35 +410,202 @@ impl encdec::DecodeOwned for EpDescriptor {
             warn!("Unrecognised option kind: {}", option_kind);
             return Err(Error::InvalidOption);
         }
-        let len = LittleEndian::read_u16(&buff[2..]) + 4;
+        let len = LittleEndian::read_u16(&buff[2..]) as usize;
+
+        // Check option length is valid and data is available
+        if len < iot_option_kinds::ENDPOINT_DESCRIPTOR_LEN || buff.len() < len + 4 {
+            warn!(
+                "Invalid descriptor length: {} (buffer: {})",
+                len,
+                buff.len()
+            );
+            return Err(Error::InvalidOption);
+        }
 
         // Parse out endpoint index and kind
         let kind = LittleEndian::read_u16(&buff[4..]).into();
         let flags = LittleEndian::read_u16(&buff[6..]);
         let flags = EpFlags::from_bits_truncate(flags);
 
-        // TODO: read metadata
+        let mut d = Self::new(kind, flags);
+
+        // Read metadata if available, older descriptors omit this
+        if len >= iot_option_kinds::ENDPOINT_DESCRIPTOR_LEN + 2 {
+            d.role = EpRole::from_u8(buff[8]);
+
+            let mask = buff[9];
+            let mut values = buff[10..4 + len]
+                .chunks_exact(4)
+                .map(LittleEndian::read_f32);
+
+            let c = &mut d.constraints;
+            for (i, v) in [&mut c.min, &mut c.max, &mut c.step].iter_mut().enumerate() {
+                if mask & (1 << i) != 0 {
+                    **v = Some(values.next().ok_or(Error::InvalidOption)?);
+                }
+            }
 
-        Ok((Self { kind, flags }, len as usize))
+            if mask & CALIBRATION_MASK != 0 {
+                let c = &mut d.calibration;
+                for v in [&mut c.offset, &mut c.scale, &mut c.quadratic, &mut c.cubic] {
+                    *v = values.next().ok_or(Error::InvalidOption)?;
+                }
+            }
+        }
+
+        Ok((d, len + 4))
     }
 }
 
+/// Parse an endpoint descriptor in the form `KIND[:ROLE[:MIN..MAX[/STEP]]]`,
+/// actuator and config roles are writable
 pub fn parse_endpoint_descriptor(src: &str) -> Result<EpDescriptor, IotError> {
-    let kind = parse_endpoint_kind(src)?;
-    Ok(EpDescriptor::new(kind, EpFlags::empty()))
+    let mut parts = src.split(':');
+
+    let kind = parse_endpoint_kind(parts.next().unwrap_or(""))?;
+    let mut d = EpDescriptor::new(kind, EpFlags::empty());
+
+    if let Some(r) = parts.next() {
+        let role = r.parse().map_err(|_| IotError::InvalidDescriptor)?;
+
+        d.flags = match role {
+            EpRole::Actuator | EpRole::Config => EpFlags::RW,
+            EpRole::Sensor | EpRole::Event => EpFlags::R,
+        };
+        d.role = Some(role);
+    }
+
+    if let Some(c) = parts.next() {
+        d.constraints = parse_constraints(c)?;
+    }
+
+    if parts.next().is_some() {
+        return Err(IotError::InvalidDescriptor);
+    }
+
+    Ok(d)
+}
+
+/// Parse value constraints in the form `[MIN]..[MAX][/STEP]`
+fn parse_constraints(src: &str) -> Result<EpConstraints, IotError> {
+    let parse = |v: &str| match v {
+        "" => Ok(None),
+        _ => v
+            .parse::<f32>()
+            .map(Some)
+            .map_err(|_| IotError::InvalidDescriptor),
+    };
+
+    let (range, step) = match src.split_once('/') {
+        Some((r, s)) => (r, parse(s)?),
+        None => (src, None),
+    };
+    let (min, max) = range.split_once("..").ok_or(IotError::InvalidDescriptor)?;
+
+    Ok(EpConstraints {
+        min: parse(min)?,
+        max: parse(max)?,
+        step,
+    })
+}
+
+/// Endpoint data quality, allowing known-bad or estimated readings to be filtered
+#[derive(Debug, Copy, Clone, PartialEq, strum::IntoStaticStr)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[strum(serialize_all = "snake_case")]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum EpQuality {
+    /// Valid reading
+    Good = 0,
+    /// Reading of uncertain accuracy (e.g. sensor warm-up)
+    Uncertain = 1,
+    /// Known-bad reading (e.g. sensor fault, out of range)
+    Bad = 2,
+    /// Substituted value (e.g. last known good or estimated)
+    Substitute = 3,
+}
+
+impl Default for EpQuality {
+    fn default() -> Self {
+        Self::Good
+    }
+}
+
+impl EpQuality {
+    fn from_u8(v: u8) -> Option<Self> {
+        match v {
+            0 => Some(Self::Good),
+            1 => Some(Self::Uncertain),
+            2 => Some(Self::Bad),
+            3 => Some(Self::Substitute),
+            _ => None,
+        }
+    }
+}
+
+impl core::fmt::Display for EpQuality {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let s: &'static str = self.into();
+        write!(f, "{}", s)
+    }
 }
 
 /// Endpoint data object contains data associated with a specific endpoint
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct EpData {
     // Measurement value
     pub value: EpValue,
+
+    /// Measurement quality
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub quality: EpQuality,
+
+    /// Endpoint index, set for values in differential data objects
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub index: Option<u16>,
 }
 
 impl EpData {
     pub fn new(value: EpValue) -> Self {
-        Self { value }
+        Self {
+            value,
+            quality: EpQuality::Good,
+            index: None,
+        }
+    }
+
+    /// Set the measurement quality
+    pub fn with_quality(mut self, quality: EpQuality) -> Self {
+        self.quality = quality;
+        self
+    }
+
+    /// Set the endpoint index, for use in differential data objects
+    pub fn with_index(mut self, index: u16) -> Self {
+        self.index = Some(index);
+        self
+    }
+
+    /// Length of the encoded index option, omitted for full data objects
+    fn index_len(&self) -> usize {
+        match self.index {
+            Some(_) => 6,
+            None => 0,
+        }
+    }
+
+    /// Length of the encoded quality option, omitted for good readings
+    fn quality_len(&self) -> usize {
+        match self.quality {
+            EpQuality::Good => 0,
+            _ => 5,
+        }
     }
 }
 
@@ -146,38 +616,103 @@ impl encdec::DecodeOwned for EpData {
     fn decode_owned(buff: &[u8]) -> Result<(Self::Output, usize), Self::Error> {
         use iot_option_kinds::*;
 
-        // Read option header (kind and length)
-        let kind = LittleEndian::read_u16(&buff[0..]);
-        let len = LittleEndian::read_u16(&buff[2..]);
-
-        let value = match kind {
-            VALUE_BOOL_FALSE => EpValue::Bool(false),
-            VALUE_BOOL_TRUE => EpValue::Bool(true),
-            VALUE_FLOAT => {
-                let f = LittleEndian::read_f32(&buff[4..]);
+        // Index and quality options precede the value they apply to (in that order),
+        // each may occur at most once so prefixes cannot be chained
+        let mut index = None;
+        let mut quality = None;
+        let mut offset = 0;
+
+        let (kind, len, d) = loop {
+            let buff = &buff[offset..];
+
+            // Check option header is available
+            if buff.len() < 4 {
+                return Err(Error::InvalidOption);
+            }
+
+            // Read option header (kind and length)
+            let kind = LittleEndian::read_u16(&buff[0..]);
+            let len = LittleEndian::read_u16(&buff[2..]) as usize;
+
+            // Check option data is available
+            let d = match buff.get(4..4 + len) {
+                Some(d) => d,
+                None => {
+                    warn!("Option length {} exceeds buffer: {}", len, buff.len());
+                    return Err(Error::InvalidOption);
+                }
+            };
+
+            match kind {
+                DATA_INDEX if index.is_none() && quality.is_none() => {
+                    if len != 2 {
+                        return Err(Error::InvalidOption);
+                    }
+                    index = Some(LittleEndian::read_u16(d));
+                }
+                DATA_QUALITY if quality.is_none() => {
+                    let q = d
+                        .first()
+                        .and_then(|q| EpQuality::from_u8(*q))
+                        .ok_or(Error::InvalidOption)?;
+                    quality = Some(q);
+                }
+                DATA_INDEX | DATA_QUALITY => {
+                    warn!("Unexpected data prefix option: 0x{:x?}", kind);
+                    return Err(Error::InvalidOption);
+                }
+                _ => break (kind, len, d),
+            }
+
+            offset += 4 + len;
+        };
+
+        let value = match (kind, len) {
+            (VALUE_BOOL_FALSE, _) => EpValue::Bool(false),
+            (VALUE_BOOL_TRUE, _) => EpValue::Bool(true),
+            (VALUE_FLOAT, 4) => {
+                let f = LittleEndian::read_f32(d);
                 EpValue::Float32(f)
             }
-            VALUE_INT => {
-                let f = LittleEndian::read_i32(&buff[4..]);
+            (VALUE_INT, 4) => {
+                let f = LittleEndian::read_i32(d);
                 EpValue::Int32(f)
             }
-            VALUE_STRING => {
-                let s = core::str::from_utf8(&buff[4..]).unwrap();
-                EpValue::Text(String::from(s))
+            (VALUE_STRING, _) => {
+                let s = core::str::from_utf8(d).map_err(|_| Error::InvalidOption)?;
+
+                let mut v = String::new();
+                v.push_str(s).map_err(|_| Error::InvalidOption)?;
+
+                EpValue::Text(v)
+            }
+            (VALUE_RAW, _) => EpValue::try_from(d).map_err(|_| Error::InvalidOption)?,
+            (VALUE_COUNTER, 8) => {
+                let v = LittleEndian::read_u64(d);
+                EpValue::Counter64(v)
+            }
+            (VALUE_DURATION, 8) => {
+                let v = LittleEndian::read_u64(d);
+                EpValue::DurationMs(v)
             }
-            VALUE_RAW => {
-                let s = &buff[4..][..len as usize];
-                EpValue::try_from(s).map_err(|_| Error::InvalidOption)?
+            (VALUE_PERCENT, 4) => {
+                let v = LittleEndian::read_f32(d);
+                EpValue::Percent(v)
             }
+            (VALUE_EVENT, 16) => EpValue::Event {
+                count: LittleEndian::read_u64(d),
+                time_ms: LittleEndian::read_u64(&d[8..]),
+            },
             _ => {
-                error!("Unrecognised option kind: 0x{:x?}", kind);
+                error!("Unrecognised option kind: 0x{:x?} (len: {})", kind, len);
                 return Err(Error::InvalidOption);
             }
         };
 
-        // TODO: read metadata
+        let mut v = Self::new(value).with_quality(quality.unwrap_or_default());
+        v.index = index;
 
-        Ok((Self { value }, len as usize + 4))
+        Ok((v, offset + len + 4))
     }
 }
 
@@ -187,7 +722,9 @@ impl encdec::Encode for EpData {
     fn encode_len(&self) -> Result<usize, Self::Error> {
         let n = match &self.value {
             EpValue::Bool(_) => 4,
-            EpValue::Float32(_) | EpValue::Int32(_) => 8,
+            EpValue::Float32(_) | EpValue::Int32(_) | EpValue::Percent(_) => 8,
+            EpValue::Counter64(_) | EpValue::DurationMs(_) => 12,
+            EpValue::Event { .. } => 20,
             EpValue::Text(v) => {
                 let b = v.deref().as_bytes();
                 4 + b.len()
@@ -195,12 +732,30 @@ impl encdec::Encode for EpData {
             EpValue::Bytes(v) => 4 + v.len(),
         };
 
-        Ok(n)
+        Ok(self.index_len() + self.quality_len() + n)
     }
 
     fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
         use iot_option_kinds::*;
 
+        // Write index option prior to the quality and value where required
+        let i = self.index_len();
+        if let Some(index) = self.index {
+            LittleEndian::write_u16(&mut buff[0..], DATA_INDEX);
+            LittleEndian::write_u16(&mut buff[2..], 2);
+            LittleEndian::write_u16(&mut buff[4..], index);
+        }
+        let buff = &mut buff[i..];
+
+        // Write quality option prior to the value where required
+        let q = self.quality_len();
+        if q > 0 {
+            LittleEndian::write_u16(&mut buff[0..], DATA_QUALITY);
+            LittleEndian::write_u16(&mut buff[2..], 1);
+            buff[4] = self.quality as u8;
+        }
+        let buff = &mut buff[q..];
+
         // Write option header and data
         let len = match &self.value {
             EpValue::Bool(v) if *v == true => {
@@ -239,12 +794,35 @@ impl encdec::Encode for EpData {
                 (&mut buff[4..4 + v.len()]).copy_from_slice(&v);
                 4 + v.len()
             }
+            EpValue::Counter64(v) => {
+                LittleEndian::write_u16(&mut buff[0..], VALUE_COUNTER);
+                LittleEndian::write_u16(&mut buff[2..], 8);
+                LittleEndian::write_u64(&mut buff[4..], *v);
+                12
+            }
+            EpValue::DurationMs(v) => {
+                LittleEndian::write_u16(&mut buff[0..], VALUE_DURATION);
+                LittleEndian::write_u16(&mut buff[2..], 8);
+                LittleEndian::write_u64(&mut buff[4..], *v);
+                12
+            }
+            EpValue::Percent(v) => {
+                LittleEndian::write_u16(&mut buff[0..], VALUE_PERCENT);
+                LittleEndian::write_u16(&mut buff[2..], 4);
+                LittleEndian::write_f32(&mut buff[4..], *v);
+                8
+            }
+            EpValue::Event { count, time_ms } => {
+                LittleEndian::write_u16(&mut buff[0..], VALUE_EVENT);
+                LittleEndian::write_u16(&mut buff[2..], 16);
+                LittleEndian::write_u64(&mut buff[4..], *count);
+                LittleEndian::write_u64(&mut buff[12..], *time_ms);
+                20
+            }
             _ => unimplemented!("Encode not yet implemented for value: {:?}", self),
         };
 
-        // TODO: write metadata
-
-        Ok(len)
+        Ok(i + q + len)
     }
 }
 
@@ -262,24 +840,36 @@ mod tests {
     #[test]
     fn encode_decode_endpoint_descriptor() {
         let descriptors = vec![
-            EpDescriptor {
-                kind: EpKind::Temperature,
-                flags: EpFlags::R,
-            },
-            EpDescriptor {
-                kind: EpKind::Pressure,
-                flags: EpFlags::W,
-            },
-            EpDescriptor {
-                kind: EpKind::Humidity,
-                flags: EpFlags::RW,
-            },
+            EpDescriptor::new(EpKind::Temperature, EpFlags::R),
+            EpDescriptor::new(EpKind::Pressure, EpFlags::W),
+            EpDescriptor::new(EpKind::Humidity, EpFlags::RW),
+            EpDescriptor::new(EpKind::State, EpFlags::RW).with_role(EpRole::Actuator),
+            EpDescriptor::new(EpKind::Temperature, EpFlags::RW)
+                .with_role(EpRole::Config)
+                .with_constraints(EpConstraints {
+                    min: Some(5.0),
+                    max: None,
+                    step: Some(0.5),
+                }),
+            EpDescriptor::new(EpKind::Humidity, EpFlags::R)
+                .with_calibration(EpCalibration::linear(-1.5, 1.02)),
+            EpDescriptor::new(EpKind::Brightness, EpFlags::RW)
+                .with_constraints(EpConstraints {
+                    min: Some(0.0),
+                    max: Some(100.0),
+                    step: None,
+                })
+                .with_calibration(EpCalibration {
+                    quadratic: 0.01,
+                    ..Default::default()
+                }),
         ];
 
         for descriptor in &descriptors {
             let mut buff = vec![0u8; 1024];
 
             let n = descriptor.encode(&mut buff).expect("Encoding error");
+            assert_eq!(n, descriptor.encode_len().unwrap());
 
             trace!("Encoded {:?} to: {:0x?}", descriptor, &buff[..n]);
 
@@ -289,24 +879,89 @@ mod tests {
         }
     }
 
+    #[test]
+    fn endpoint_roles_constraints() {
+        let d = parse_endpoint_descriptor("temperature:config:5..30/0.5").unwrap();
+        assert_eq!(d.role(), EpRole::Config);
+        assert_eq!(d.flags, EpFlags::RW);
+        assert_eq!(d.constraints.to_string(), "5..30/0.5");
+
+        assert!(d.validate(&EpValue::Float32(21.5)).is_ok());
+        assert!(matches!(
+            d.validate(&EpValue::Float32(21.2)),
+            Err(IotError::OutOfRange)
+        ));
+        assert!(matches!(
+            d.validate(&EpValue::Float32(31.0)),
+            Err(IotError::OutOfRange)
+        ));
+        assert!(matches!(
+            d.validate(&EpValue::Bool(true)),
+            Err(IotError::MismatchedType)
+        ));
+
+        let d = parse_endpoint_descriptor("humidity").unwrap();
+        assert_eq!(d.role(), EpRole::Sensor);
+        assert!(matches!(
+            d.validate(&EpValue::Float32(1.0)),
+            Err(IotError::ReadOnly)
+        ));
+
+        assert!(parse_endpoint_descriptor("state:switch").is_err());
+        assert!(parse_endpoint_descriptor("state:actuator:1").is_err());
+    }
+
+    #[test]
+    fn endpoint_calibration() {
+        let c = EpCalibration::linear(-1.5, 2.0);
+        assert_eq!(c.apply(&EpValue::Float32(10.0)), EpValue::Float32(18.5));
+        assert_eq!(c.apply(&EpValue::Int32(1)), EpValue::Float32(0.5));
+        assert_eq!(c.apply(&EpValue::Percent(50.0)), EpValue::Percent(98.5));
+        assert_eq!(c.apply(&EpValue::Bool(true)), EpValue::Bool(true));
+        let e = EpValue::Event {
+            count: 2,
+            time_ms: 1000,
+        };
+        assert_eq!(c.apply(&e), e);
+        assert_eq!(c.to_string(), "2·x-1.5");
+
+        let c = EpCalibration {
+            cubic: 1.0,
+            ..Default::default()
+        };
+        assert_eq!(c.apply_f32(2.0), 10.0);
+
+        let c = EpCalibration::default();
+        assert!(c.is_identity());
+        assert_eq!(c.apply(&EpValue::Int32(3)), EpValue::Int32(3));
+    }
+
     #[test]
     fn encode_decode_endpoint_data() {
         let data = vec![
-            EpData {
-                value: EpValue::Bool(true),
-            },
-            EpData {
-                value: EpValue::Bool(false),
-            },
-            EpData {
-                value: EpValue::Float32(10.45),
-            },
+            EpData::new(EpValue::Bool(true)),
+            EpData::new(EpValue::Bool(false)),
+            EpData::new(EpValue::Float32(10.45)),
+            EpData::new(EpValue::Counter64(u64::MAX - 3)),
+            EpData::new(EpValue::DurationMs(3_600_123)),
+            EpData::new(EpValue::Percent(42.5)),
+            EpData::new(EpValue::Event {
+                count: 12,
+                time_ms: 1_700_000_000_000,
+            }),
+            EpData::new(EpValue::Float32(-1.0)).with_quality(EpQuality::Bad),
+            EpData::new(EpValue::Bool(true)).with_quality(EpQuality::Substitute),
+            EpData::new(EpValue::Int32(7)).with_index(3),
+            EpData::new(EpValue::Percent(1.5))
+                .with_quality(EpQuality::Uncertain)
+                .with_index(12),
         ];
 
         for d in &data {
             let mut buff = vec![0u8; 1024];
 
             let n = d.encode(&mut buff).expect("Encoding error");
+            assert_eq!(n, d.encode_len().unwrap());
 
             trace!("Encoded {:?} to: {:0x?}", d, &buff[..n]);
 
@@ -315,4 +970,123 @@ mod tests {
             assert_eq!(d, &d1);
         }
     }
+
+    #[test]
+    fn decode_truncated_endpoint_data() {
+        let data = vec![
+            EpData::new(EpValue::Bool(true)),
+            EpData::new(EpValue::Float32(10.45)),
+            EpData::new(EpValue::Int32(-7)),
+            EpData::new(EpValue::from("some text")),
+            EpData::new(EpValue::try_from(&[1u8, 2, 3, 4]).unwrap()),
+            EpData::new(EpValue::Counter64(17)),
+            EpData::new(EpValue::Float32(2.5)).with_index(4),
+        ];
+
+        for d in &data {
+            let mut buff = vec![0u8; 1024];
+            let n = d.encode(&mut buff).expect("Encoding error");
+
+            // Every truncation of the encoded object must fail to decode
+            for i in 0..n {
+                assert!(
+                    matches!(EpData::decode(&buff[..i]), Err(Error::InvalidOption)),
+                    "decoding {:?} truncated to {} bytes",
+                    d,
+                    i
+                );
+            }
+        }
+
+        let d = EpDescriptor::new(EpKind::Temperature, EpFlags::R);
+        let mut buff = vec![0u8; 1024];
+        let n = d.encode(&mut buff).expect("Encoding error");
+
+        for i in 0..n {
+            assert!(EpDescriptor::decode(&buff[..i]).is_err());
+        }
+    }
+
+    #[test]
+    fn decode_invalid_endpoint_data() {
+        use iot_option_kinds::*;
+
+        // Invalid UTF-8 in string value
+        let mut buff = [0u8; 6];
+        LittleEndian::write_u16(&mut buff[0..], VALUE_STRING);
+        LittleEndian::write_u16(&mut buff[2..], 2);
+        buff[4..].copy_from_slice(&[0xc3, 0x28]);
+        assert!(matches!(EpData::decode(&buff), Err(Error::InvalidOption)));
+
+        // Mismatched length for fixed size value
+        let mut buff = [0u8; 6];
+        LittleEndian::write_u16(&mut buff[0..], VALUE_FLOAT);
+        LittleEndian::write_u16(&mut buff[2..], 2);
+        assert!(matches!(EpData::decode(&buff), Err(Error::InvalidOption)));
+
+        // String exceeding value capacity
+        let mut buff = [b'a'; 100];
+        LittleEndian::write_u16(&mut buff[0..], VALUE_STRING);
+        LittleEndian::write_u16(&mut buff[2..], 96);
+        assert!(matches!(EpData::decode(&buff), Err(Error::InvalidOption)));
+    }
+
+    #[test]
+    fn decode_chained_prefixes() {
+        use iot_option_kinds::*;
+
+        let quality = |b: &mut Vec<u8>| b.extend_from_slice(&[0, 0, 1, 0, 1]);
+        let index = |b: &mut Vec<u8>| b.extend_from_slice(&[0, 0, 2, 0, 3, 0]);
+        let value = |b: &mut Vec<u8>| b.extend_from_slice(&[0, 0, 0, 0]);
+
+        let patch = |mut b: Vec<u8>, kinds: &[u16]| {
+            let mut n = 0;
+            for k in kinds {
+                LittleEndian::write_u16(&mut b[n..], *k);
+                n += 4 + LittleEndian::read_u16(&b[n + 2..]) as usize;
+            }
+            b
+        };
+
+        // Repeated or out of order prefixes are rejected
+        let mut b = vec![];
+        quality(&mut b);
+        quality(&mut b);
+        value(&mut b);
+        let b = patch(b, &[DATA_QUALITY, DATA_QUALITY, VALUE_BOOL_TRUE]);
+        assert!(matches!(EpData::decode(&b), Err(Error::InvalidOption)));
+
+        let mut b = vec![];
+        quality(&mut b);
+        index(&mut b);
+        value(&mut b);
+        let b = patch(b, &[DATA_QUALITY, DATA_INDEX, VALUE_BOOL_TRUE]);
+        assert!(matches!(EpData::decode(&b), Err(Error::InvalidOption)));
+
+        // Long chains of prefixes fail without recursion
+        let mut b = vec![];
+        for _i in 0..10_000 {
+            quality(&mut b);
+        }
+        let kinds = vec![DATA_QUALITY; 10_000];
+        let b = patch(b, &kinds);
+        for i in [0, 3, 5, 9, b.len()] {
+            assert!(matches!(EpData::decode(&b[..i]), Err(Error::InvalidOption)));
+        }
+
+        // Index and quality prefixes apply to the following value
+        let mut b = vec![];
+        index(&mut b);
+        quality(&mut b);
+        value(&mut b);
+        let b = patch(b, &[DATA_INDEX, DATA_QUALITY, VALUE_BOOL_TRUE]);
+        let (d, n) = EpData::decode(&b).unwrap();
+        assert_eq!(n, b.len());
+        assert_eq!(
+            d,
+            EpData::new(EpValue::Bool(true))
+                .with_quality(EpQuality::Uncertain)
+                .with_index(3)
+        );
+    }
 }
@@ -0,0 +1,165 @@
+//! Standard diagnostics endpoints (battery, signal strength, uptime and
+//! error count) for consistent fleet health monitoring.
+//!
+//! Diagnostics endpoints follow the service endpoints in the primary page,
+//! with diagnostics values appended to published data objects once per
+//! diagnostics period. Data objects published between periods omit the
+//! trailing diagnostics values.
+
+use super::desc::*;
+use super::kinds::*;
+use super::value::*;
+use super::IotData;
+
+use crate::error::IotError;
+
+/// Standard diagnostics endpoint kinds, in publishing order
+pub const DIAGNOSTIC_KINDS: [EpKind; 4] = [
+    EpKind::Battery,
+    EpKind::Rssi,
+    EpKind::Uptime,
+    EpKind::ErrorCount,
+];
+
+/// Build descriptors for the standard diagnostics endpoints
+pub fn diagnostic_descriptors() -> [EpDescriptor; 4] {
+    DIAGNOSTIC_KINDS.map(|k| EpDescriptor::new(k, EpFlags::R))
+}
+
+/// Diagnostics state, appending diagnostics values to data objects
+/// every `period_ms`
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Diagnostics {
+    /// Diagnostics publishing period in milliseconds
+    pub period_ms: u64,
+    battery: Option<f32>,
+    rssi: Option<i32>,
+    errors: u64,
+    last_ms: Option<u64>,
+}
+
+impl Diagnostics {
+    /// Create a new diagnostics tracker with the provided period
+    pub fn new(period_ms: u64) -> Self {
+        Self {
+            period_ms,
+            ..Default::default()
+        }
+    }
+
+    /// Set the current battery level (in %)
+    pub fn set_battery(&mut self, percent: f32) {
+        self.battery = Some(percent);
+    }
+
+    /// Set the current link signal strength (in dBm)
+    pub fn set_rssi(&mut self, dbm: i32) {
+        self.rssi = Some(dbm);
+    }
+
+    /// Record an error, incrementing the error count
+    pub fn record_error(&mut self) {
+        self.errors = self.errors.wrapping_add(1);
+    }
+
+    /// Fetch the number of recorded errors
+    pub fn errors(&self) -> u64 {
+        self.errors
+    }
+
+    /// Check whether diagnostics are due at `now_ms`
+    pub fn due(&self, now_ms: u64) -> bool {
+        match self.last_ms {
+            Some(l) => now_ms.saturating_sub(l) >= self.period_ms,
+            None => true,
+        }
+    }
+
+    /// Build diagnostics values, with `now_ms` as milliseconds since device start.
+    ///
+    /// Battery and RSSI values that have not been set are reported with
+    /// [`EpQuality::Bad`].
+    pub fn values(&self, now_ms: u64) -> [EpData; 4] {
+        let battery = match self.battery {
+            Some(b) => EpData::new(EpValue::Percent(b)),
+            None => EpData::new(EpValue::Percent(0.0)).with_quality(EpQuality::Bad),
+        };
+        let rssi = match self.rssi {
+            Some(r) => EpData::new(EpValue::Int32(r)),
+            None => EpData::new(EpValue::Int32(0)).with_quality(EpQuality::Bad),
+        };
+
+        [
+            battery,
+            rssi,
+            EpData::new(EpValue::DurationMs(now_ms)),
+            EpData::new(EpValue::Counter64(self.errors)),
+        ]
+    }
+
+    /// Append diagnostics values to a data object where due, returning
+    /// whether diagnostics were included.
+    ///
+    /// `data` must contain values for all service endpoints preceding
+    /// the diagnostics endpoints.
+    pub fn append<const N: usize>(
+        &mut self,
+        data: &mut IotData<N>,
+        now_ms: u64,
+    ) -> Result<bool, IotError> {
+        if !self.due(now_ms) {
+            return Ok(false);
+        }
+
+        let values = self.values(now_ms);
+        if data.data.len() + values.len() > N {
+            return Err(IotError::TooManyEndpoints {
+                count: data.data.len() + values.len(),
+                max: N,
+            });
+        }
+
+        for v in values {
+            let _ = data.data.push(v);
+        }
+        self.last_ms = Some(now_ms);
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diagnostics_period() {
+        let mut d = Diagnostics::new(60_000);
+        d.set_battery(87.5);
+        d.record_error();
+
+        let mut data = IotData::<8>::new(&[EpData::new(EpValue::Float32(21.0))]).unwrap();
+        assert!(d.append(&mut data, 1_000).unwrap());
+        assert_eq!(
+            &data.data[1..],
+            &[
+                EpData::new(EpValue::Percent(87.5)),
+                EpData::new(EpValue::Int32(0)).with_quality(EpQuality::Bad),
+                EpData::new(EpValue::DurationMs(1_000)),
+                EpData::new(EpValue::Counter64(1)),
+            ]
+        );
+
+        // Not due until the period has elapsed
+        let mut data = IotData::<8>::new(&[EpData::new(EpValue::Float32(21.0))]).unwrap();
+        assert!(!d.append(&mut data, 30_000).unwrap());
+        assert_eq!(data.data.len(), 1);
+        assert!(d.append(&mut data, 61_000).unwrap());
+        assert_eq!(data.data.len(), 5);
+
+        // Diagnostics must fit the data object
+        let mut data = IotData::<4>::new(&[EpData::new(EpValue::Float32(21.0))]).unwrap();
+        assert!(d.append(&mut data, 200_000).is_err());
+    }
+}
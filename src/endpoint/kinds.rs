@@ -1,3 +1,4 @@
+#[cfg(any(feature = "std", feature = "alloc"))]
 use core::fmt::Write;
 use core::str::FromStr;
 
@@ -18,11 +19,18 @@ pub const ENDPOINT_KINDS: &[(u16, EpKind, &str, &str)] = &[
     (5, EpKind::State, "state", "bool"),
     (6, EpKind::Brightness, "brightness", "%"),
     (7, EpKind::Colour, "colour", "rgb"),
+    (8, EpKind::Battery, "battery", "%"),
+    (9, EpKind::Rssi, "rssi", "dBm"),
+    (10, EpKind::Uptime, "uptime", "s"),
+    (11, EpKind::ErrorCount, "error_count", "count"),
+    (12, EpKind::Event, "event", "count"),
+    (13, EpKind::Power, "power", "W"),
+    (14, EpKind::Energy, "energy", "kWh"),
 ];
 
 /// [`Kind`] specifies the type of IoT endpoint, translated using the [`ENDPOINT_KINDS`] table
 /// For example: Temperature, Heart-Rate
-#[derive(Debug, Copy, Clone, PartialEq, strum::Display)]
+#[derive(Debug, Copy, Clone, PartialEq, strum::IntoStaticStr)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[strum(serialize_all = "snake_case")]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -42,22 +50,39 @@ pub enum EpKind {
     Brightness,
     /// RGB encoded colour
     Colour,
+    /// Battery level as a percentage
+    Battery,
+    /// Received signal strength (in dBm)
+    Rssi,
+    /// Device uptime
+    Uptime,
+    /// Device error count
+    ErrorCount,
+    /// Event / trigger (e.g. motion or button press), with edge rather than level semantics
+    Event,
+    /// Power (in W)
+    Power,
+    /// Cumulative energy (in kWh)
+    Energy,
     /// Unknown measurement kind (no units)
     Unknown(u16),
 }
 
 /// Parse an endpoint kind from a string
 pub fn parse_endpoint_kind(src: &str) -> Result<EpKind, IotError> {
-    // Coerce to lower case
-    let src = src.to_lowercase();
-
-    // Attempt to find matching endpoint name
-    if let Ok(v) = EpKind::from_str(&src) {
+    // Attempt to find matching endpoint name (case insensitive)
+    if let Ok(v) = EpKind::from_str(src) {
         return Ok(v);
     }
 
+    // Attempt to find matching registered endpoint
+    #[cfg(feature = "std")]
+    if let Some(d) = super::registry::find_name(src) {
+        return Ok(EpKind::Unknown(d.id));
+    }
+
     // Attempt to parse as an integer
-    if let Ok(v) = u16::from_str(&src) {
+    if let Ok(v) = u16::from_str(src) {
         return Ok(EpKind::Unknown(v));
     }
 
@@ -66,6 +91,7 @@ pub fn parse_endpoint_kind(src: &str) -> Result<EpKind, IotError> {
 
 impl EpKind {
     /// List available endpoint variants
+    #[cfg(any(feature = "std", feature = "alloc"))]
     pub fn variants() -> String {
         let mut buff = String::new();
 
@@ -73,26 +99,59 @@ impl EpKind {
             write!(&mut buff, "'{}' (unit: {}, id: {}), ", s, u, i).unwrap();
         }
 
+        #[cfg(feature = "std")]
+        for d in super::registry::kinds() {
+            write!(&mut buff, "'{}' (unit: {}, id: {}), ", d.name, d.unit, d.id).unwrap();
+        }
+
         write!(&mut buff, "RAW_ID (no unit)").unwrap();
 
         buff
     }
 
+    /// Fetch the unit for an endpoint kind, including registered kinds
+    #[cfg(any(feature = "std", feature = "alloc"))]
     pub fn unit(&self) -> String {
+        #[cfg(feature = "std")]
+        if let EpKind::Unknown(v) = self {
+            if let Some(d) = super::registry::find_id(*v) {
+                return d.unit;
+            }
+        }
+
+        self.base_unit().to_string()
+    }
+
+    /// Fetch the unit for a standard endpoint kind, without allocation
+    pub fn base_unit(&self) -> &'static str {
         match ENDPOINT_KINDS.iter().find(|(_i, k, _s, _u)| k == self) {
-            Some(e) => e.3.to_string(),
-            None => "unknown".to_string(),
+            Some(e) => e.3,
+            None => "unknown",
         }
     }
 }
 
+impl core::fmt::Display for EpKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        // Use registered names for otherwise unknown endpoints
+        #[cfg(feature = "std")]
+        if let EpKind::Unknown(v) = self {
+            if let Some(d) = super::registry::find_id(*v) {
+                return f.pad(&d.name);
+            }
+        }
+
+        f.pad(<&'static str>::from(self))
+    }
+}
+
 impl core::str::FromStr for EpKind {
     type Err = &'static str;
 
     fn from_str(src: &str) -> Result<Self, Self::Err> {
         match ENDPOINT_KINDS
             .iter()
-            .find(|(_i, _k, s, _u)| src.to_lowercase() == *s.to_lowercase())
+            .find(|(_i, _k, s, _u)| s.eq_ignore_ascii_case(src))
         {
             Some(e) => Ok(e.1),
             None => Err("No matching endpoint name found"),
@@ -126,3 +185,54 @@ impl From<&EpKind> for u16 {
         unreachable!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoint_kind_mappings() {
+        for (i, k, s, u) in ENDPOINT_KINDS {
+            // ID <-> kind
+            assert_eq!(u16::from(k), *i, "kind {:?} -> id", k);
+            assert_eq!(EpKind::from(*i), *k, "id {} -> kind", i);
+
+            // Name <-> kind
+            assert_eq!(EpKind::from_str(s), Ok(*k), "name {} -> kind", s);
+            assert_eq!(parse_endpoint_kind(s).unwrap(), *k, "parse {}", s);
+            assert_eq!(parse_endpoint_kind(&s.to_uppercase()).unwrap(), *k);
+            assert!(
+                k.to_string().eq_ignore_ascii_case(s),
+                "kind {:?} -> name",
+                k
+            );
+
+            // Units
+            assert_eq!(k.unit(), *u, "kind {:?} -> unit", k);
+            assert_eq!(k.base_unit(), *u, "kind {:?} -> base unit", k);
+        }
+    }
+
+    #[test]
+    fn endpoint_kind_collisions() {
+        for (n, (i, k, s, _u)) in ENDPOINT_KINDS.iter().enumerate() {
+            for (i1, k1, s1, _u1) in &ENDPOINT_KINDS[n + 1..] {
+                assert_ne!(i, i1, "duplicate id for {:?} and {:?}", k, k1);
+                assert_ne!(k, k1, "duplicate kind {:?}", k);
+                assert!(!s.eq_ignore_ascii_case(s1), "duplicate name {}", s);
+            }
+
+            // Table entries must not use the unknown variant
+            assert!(!matches!(k, EpKind::Unknown(_)), "unknown kind in table");
+        }
+    }
+
+    #[test]
+    fn endpoint_kind_unknown() {
+        let k = EpKind::from(0xeeee);
+        assert_eq!(k, EpKind::Unknown(0xeeee));
+        assert_eq!(u16::from(&k), 0xeeee);
+        assert_eq!(parse_endpoint_kind("61166").unwrap(), k);
+        assert_eq!(k.unit(), "unknown");
+    }
+}
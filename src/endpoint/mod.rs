@@ -12,22 +12,53 @@ pub use value::*;
 pub mod desc;
 pub use desc::*;
 
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod convert;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub use convert::*;
+
+pub mod readings;
+pub use readings::*;
+
+pub mod derived;
+pub use derived::*;
+
+pub mod diag;
+pub use diag::*;
+
+pub mod delta;
+pub use delta::*;
+
+pub mod class;
+pub use class::*;
+
+#[cfg(feature = "std")]
+pub mod registry;
+
 use crate::prelude::IotError;
 
+/// Default maximum number of endpoints per service, larger limits may be
+/// used (e.g. for gateways) by specifying `N` for [`IotInfo`] / [`IotData`]
+/// and [`crate::IotApp`]
+pub const MAX_ENDPOINTS: usize = 8;
+
 /// IoT information object containing endpoint descriptors and service metadata
 #[derive(Debug, Encode, DecodeOwned)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[encdec(error = "IotError")]
-pub struct IotInfo<const N: usize = 8> {
+pub struct IotInfo<const N: usize = MAX_ENDPOINTS> {
     pub descriptors: Vec<EpDescriptor, N>,
 }
 
 impl<const N: usize> IotInfo<N> {
     /// Create a new [`IotInfo`] object with the provided descriptors
-    pub fn new(descriptors: &[EpDescriptor]) -> Result<Self, ()> {
-        Ok(Self {
-            descriptors: Vec::from_slice(descriptors)?,
-        })
+    pub fn new(descriptors: &[EpDescriptor]) -> Result<Self, IotError> {
+        let descriptors = Vec::from_slice(descriptors).map_err(|_| IotError::TooManyEndpoints {
+            count: descriptors.len(),
+            max: N,
+        })?;
+
+        Ok(Self { descriptors })
     }
 }
 
@@ -38,7 +69,12 @@ impl<const N: usize> core::fmt::Display for IotInfo<N> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         for i in 0..self.descriptors.len() {
             let e = &self.descriptors[i];
-            writeln!(f, "  - {:2}: {:16} in {:4}", i, e.kind, e.kind.unit())?;
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            let unit = e.kind.unit();
+            #[cfg(not(any(feature = "std", feature = "alloc")))]
+            let unit = e.kind.base_unit();
+
+            writeln!(f, "  - {:2}: {:16} in {:4}", i, e.kind, unit)?;
         }
         Ok(())
     }
@@ -54,16 +90,20 @@ impl<const N: usize> Default for IotInfo<N> {
 #[derive(Debug, Encode, DecodeOwned)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[encdec(error = "IotError")]
-pub struct IotData<const N: usize = 8> {
+pub struct IotData<const N: usize = MAX_ENDPOINTS> {
     /// Measurement values (these must correspond with service endpoints)
     pub data: Vec<EpData, N>,
 }
 
 impl<const N: usize> IotData<N> {
-    pub fn new(data: &[EpData]) -> Result<Self, ()> {
-        Ok(Self {
-            data: Vec::from_slice(data)?,
-        })
+    /// Create a new [`IotData`] object with the provided values
+    pub fn new(data: &[EpData]) -> Result<Self, IotError> {
+        let data = Vec::from_slice(data).map_err(|_| IotError::TooManyEndpoints {
+            count: data.len(),
+            max: N,
+        })?;
+
+        Ok(Self { data })
     }
 }
 
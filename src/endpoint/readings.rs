@@ -0,0 +1,177 @@
+use core::convert::TryFrom;
+
+use super::desc::*;
+use super::kinds::*;
+use super::value::*;
+use super::{IotData, IotInfo};
+use crate::prelude::IotError;
+
+/// Endpoint readings, pairing endpoint descriptors with their corresponding data
+/// to provide typed lookups by endpoint kind
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EpReadings<'a> {
+    descriptors: &'a [EpDescriptor],
+    data: &'a [EpData],
+}
+
+impl<'a> EpReadings<'a> {
+    /// Create readings from descriptor and data slices
+    pub fn new(descriptors: &'a [EpDescriptor], data: &'a [EpData]) -> Self {
+        Self { descriptors, data }
+    }
+
+    /// Iterate over descriptor and value pairs, values without
+    /// matching descriptors are skipped
+    pub fn iter(&self) -> impl Iterator<Item = (&'a EpDescriptor, &'a EpValue)> {
+        self.descriptors
+            .iter()
+            .zip(self.data.iter().map(|d| &d.value))
+    }
+
+    /// Iterate over descriptor and value pairs, applying descriptor calibration
+    pub fn calibrated(&self) -> impl Iterator<Item = (&'a EpDescriptor, EpValue)> {
+        self.iter().map(|(d, v)| (d, d.calibration.apply(v)))
+    }
+
+    /// Fetch the first value for the provided endpoint kind
+    pub fn value(&self, kind: EpKind) -> Option<&'a EpValue> {
+        self.value_nth(kind, 0)
+    }
+
+    /// Fetch the nth value for the provided endpoint kind
+    pub fn value_nth(&self, kind: EpKind, n: usize) -> Option<&'a EpValue> {
+        self.iter()
+            .filter(|(d, _v)| d.kind == kind)
+            .nth(n)
+            .map(|(_d, v)| v)
+    }
+
+    /// Fetch the first value for the provided endpoint kind as type `T`
+    pub fn get<T>(&self, kind: EpKind) -> Result<T, IotError>
+    where
+        T: TryFrom<&'a EpValue, Error = IotError>,
+    {
+        self.get_nth(kind, 0)
+    }
+
+    /// Fetch the nth value for the provided endpoint kind as type `T`
+    pub fn get_nth<T>(&self, kind: EpKind, n: usize) -> Result<T, IotError>
+    where
+        T: TryFrom<&'a EpValue, Error = IotError>,
+    {
+        match self.value_nth(kind, n) {
+            Some(v) => T::try_from(v),
+            None => Err(IotError::NoEndpoint),
+        }
+    }
+}
+
+impl<const N: usize> IotInfo<N> {
+    /// Pair endpoint descriptors with the provided data
+    pub fn readings<'a, const M: usize>(&'a self, data: &'a IotData<M>) -> EpReadings<'a> {
+        EpReadings::new(&self.descriptors, &data.data)
+    }
+}
+
+impl TryFrom<&EpValue> for f32 {
+    type Error = IotError;
+
+    fn try_from(v: &EpValue) -> Result<Self, Self::Error> {
+        match v {
+            EpValue::Float32(v) | EpValue::Percent(v) => Ok(*v),
+            _ => Err(IotError::MismatchedType),
+        }
+    }
+}
+
+impl TryFrom<&EpValue> for i32 {
+    type Error = IotError;
+
+    fn try_from(v: &EpValue) -> Result<Self, Self::Error> {
+        match v {
+            EpValue::Int32(v) => Ok(*v),
+            _ => Err(IotError::MismatchedType),
+        }
+    }
+}
+
+impl TryFrom<&EpValue> for u64 {
+    type Error = IotError;
+
+    fn try_from(v: &EpValue) -> Result<Self, Self::Error> {
+        match v {
+            EpValue::Counter64(v) | EpValue::DurationMs(v) => Ok(*v),
+            _ => Err(IotError::MismatchedType),
+        }
+    }
+}
+
+impl TryFrom<&EpValue> for bool {
+    type Error = IotError;
+
+    fn try_from(v: &EpValue) -> Result<Self, Self::Error> {
+        match v {
+            EpValue::Bool(v) => Ok(*v),
+            _ => Err(IotError::MismatchedType),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a EpValue> for &'a str {
+    type Error = IotError;
+
+    fn try_from(v: &'a EpValue) -> Result<Self, Self::Error> {
+        match v {
+            EpValue::Text(v) => Ok(v.as_str()),
+            _ => Err(IotError::MismatchedType),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a EpValue> for &'a [u8] {
+    type Error = IotError;
+
+    fn try_from(v: &'a EpValue) -> Result<Self, Self::Error> {
+        match v {
+            EpValue::Bytes(v) => Ok(v.as_slice()),
+            _ => Err(IotError::MismatchedType),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn readings_lookup() {
+        let descriptors = [
+            EpDescriptor::new(EpKind::Temperature, EpFlags::R),
+            EpDescriptor::new(EpKind::Humidity, EpFlags::R),
+            EpDescriptor::new(EpKind::Temperature, EpFlags::R),
+            EpDescriptor::new(EpKind::State, EpFlags::RW),
+        ];
+        let data = [
+            EpData::new(EpValue::Float32(21.5)),
+            EpData::new(EpValue::Float32(48.0)),
+            EpData::new(EpValue::Float32(19.0)),
+            EpData::new(EpValue::Bool(true)),
+        ];
+
+        let r = EpReadings::new(&descriptors, &data);
+
+        assert_eq!(r.get::<f32>(EpKind::Temperature).unwrap(), 21.5);
+        assert_eq!(r.get_nth::<f32>(EpKind::Temperature, 1).unwrap(), 19.0);
+        assert_eq!(r.get::<bool>(EpKind::State).unwrap(), true);
+
+        assert!(matches!(
+            r.get::<f32>(EpKind::Co2),
+            Err(IotError::NoEndpoint)
+        ));
+        assert!(matches!(
+            r.get::<i32>(EpKind::Humidity),
+            Err(IotError::MismatchedType)
+        ));
+    }
+}
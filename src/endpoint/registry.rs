@@ -0,0 +1,245 @@
+//! Runtime registry of additional endpoint kinds, extending the static
+//! [`ENDPOINT_KINDS`] table with vendor or application specific entries.
+//!
+//! Registered kinds are represented as [`EpKind::Unknown`] on the wire, the
+//! registry only provides names and units for parsing and display.
+
+use std::sync::RwLock;
+
+use super::kinds::ENDPOINT_KINDS;
+use crate::prelude::IotError;
+
+/// Runtime endpoint kind definition
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct KindDefinition {
+    /// Endpoint kind ID
+    pub id: u16,
+    /// Endpoint kind name
+    pub name: String,
+    /// Endpoint unit
+    pub unit: String,
+}
+
+/// Set of registered endpoint kinds
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KindRegistry {
+    kinds: Vec<KindDefinition>,
+}
+
+impl KindRegistry {
+    /// Create an empty kind registry
+    pub const fn new() -> Self {
+        Self { kinds: Vec::new() }
+    }
+
+    /// Register a set of endpoint kinds. All definitions are checked against
+    /// built-in kinds, registered kinds and each other before any are
+    /// registered, so a failure leaves the registry unchanged.
+    pub fn register_all(&mut self, defs: Vec<KindDefinition>) -> Result<usize, IotError> {
+        for (n, def) in defs.iter().enumerate() {
+            // Check for collisions with built-in kinds
+            if ENDPOINT_KINDS
+                .iter()
+                .any(|(i, _k, s, _u)| *i == def.id || s.eq_ignore_ascii_case(&def.name))
+            {
+                return Err(IotError::DuplicateEndpoint(def.id));
+            }
+
+            // Check for collisions with registered and preceding kinds
+            if self
+                .kinds
+                .iter()
+                .chain(&defs[..n])
+                .any(|d| d.id == def.id || d.name.eq_ignore_ascii_case(&def.name))
+            {
+                return Err(IotError::DuplicateEndpoint(def.id));
+            }
+        }
+
+        let n = defs.len();
+        self.kinds.extend(defs);
+
+        Ok(n)
+    }
+
+    /// Find a registered endpoint kind by ID
+    pub fn find_id(&self, id: u16) -> Option<&KindDefinition> {
+        self.kinds.iter().find(|d| d.id == id)
+    }
+
+    /// Find a registered endpoint kind by name (case insensitive)
+    pub fn find_name(&self, name: &str) -> Option<&KindDefinition> {
+        self.kinds
+            .iter()
+            .find(|d| d.name.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Registered endpoint kinds
+static REGISTRY: RwLock<KindRegistry> = RwLock::new(KindRegistry::new());
+
+/// Register an additional endpoint kind, IDs and names must not collide
+/// with built-in or previously registered kinds
+pub fn register(def: KindDefinition) -> Result<(), IotError> {
+    register_all(vec![def]).map(|_| ())
+}
+
+/// Register a set of endpoint kinds, registering none if any collide
+pub fn register_all(defs: Vec<KindDefinition>) -> Result<usize, IotError> {
+    REGISTRY.write().unwrap().register_all(defs)
+}
+
+/// Register an additional endpoint kind by ID, name, and unit
+pub fn register_kind(id: u16, name: &str, unit: &str) -> Result<(), IotError> {
+    register(KindDefinition {
+        id,
+        name: name.to_string(),
+        unit: unit.to_string(),
+    })
+}
+
+/// Find a registered endpoint kind by ID
+pub fn find_id(id: u16) -> Option<KindDefinition> {
+    REGISTRY.read().unwrap().find_id(id).cloned()
+}
+
+/// Find a registered endpoint kind by name (case insensitive)
+pub fn find_name(name: &str) -> Option<KindDefinition> {
+    REGISTRY.read().unwrap().find_name(name).cloned()
+}
+
+/// Fetch all registered endpoint kinds
+pub fn kinds() -> Vec<KindDefinition> {
+    REGISTRY.read().unwrap().kinds.clone()
+}
+
+/// Kind definition file, containing a list of `[[kinds]]` tables (TOML)
+/// or a `kinds` array (JSON)
+#[cfg(feature = "client")]
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+struct KindFile {
+    #[serde(default)]
+    kinds: Vec<KindDefinition>,
+}
+
+/// Check whether a definitions file uses JSON (by extension), otherwise TOML
+#[cfg(feature = "client")]
+fn is_json(path: &std::path::Path) -> bool {
+    path.extension()
+        .map(|e| e.eq_ignore_ascii_case("json"))
+        .unwrap_or(false)
+}
+
+/// Parse endpoint kind definitions from TOML or JSON
+#[cfg(feature = "client")]
+fn parse_file(s: &str, json: bool) -> Result<Vec<KindDefinition>, IotError> {
+    let f: KindFile = match json {
+        true => serde_json::from_str(s)?,
+        false => toml::from_str(s)?,
+    };
+    Ok(f.kinds)
+}
+
+/// Load and register endpoint kinds from a TOML or JSON (`.json`)
+/// definitions file, returning the number of kinds registered.
+/// No kinds are registered if any definition is invalid.
+///
+/// ```toml
+/// [[kinds]]
+/// id = 4096
+/// name = "wind_speed"
+/// unit = "m/s"
+/// ```
+#[cfg(feature = "client")]
+pub fn load_file<P: AsRef<std::path::Path>>(path: P) -> Result<usize, IotError> {
+    let path = path.as_ref();
+    let s = std::fs::read_to_string(path)?;
+
+    register_all(parse_file(&s, is_json(path))?)
+}
+
+/// Write all registered endpoint kinds to a TOML or JSON (`.json`) definitions file
+#[cfg(feature = "client")]
+pub fn save_file<P: AsRef<std::path::Path>>(path: P) -> Result<(), IotError> {
+    let path = path.as_ref();
+    let f = KindFile { kinds: kinds() };
+    let s = match is_json(path) {
+        true => serde_json::to_string_pretty(&f)?,
+        false => toml::to_string(&f)?,
+    };
+
+    if let Some(p) = path.parent() {
+        std::fs::create_dir_all(p)?;
+    }
+    std::fs::write(path, s)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoint::{parse_endpoint_kind, EpKind};
+
+    fn def(id: u16, name: &str, unit: &str) -> KindDefinition {
+        KindDefinition {
+            id,
+            name: name.to_string(),
+            unit: unit.to_string(),
+        }
+    }
+
+    // The only test using the global registry, others use local registries
+    #[test]
+    fn register_lookup_kind() {
+        register(def(0xf001, "wind_speed", "m/s")).unwrap();
+
+        let k = parse_endpoint_kind("Wind_Speed").unwrap();
+        assert_eq!(k, EpKind::Unknown(0xf001));
+        assert_eq!(k.to_string(), "wind_speed");
+        assert_eq!(k.unit(), "m/s");
+    }
+
+    #[test]
+    fn register_collisions() {
+        let mut r = KindRegistry::new();
+
+        // Built-in name and ID
+        let builtin = def(0xf002, "temperature", "K");
+        assert!(r.register_all(vec![builtin]).is_err());
+        assert!(r.register_all(vec![def(1, "kelvin", "K")]).is_err());
+
+        let k = def(0xf003, "rain", "mm");
+        r.register_all(vec![k.clone()]).unwrap();
+        assert!(r.register_all(vec![k]).is_err());
+    }
+
+    #[test]
+    fn register_all_atomic() {
+        let mut r = KindRegistry::new();
+
+        // Later collisions (here within the set) register nothing
+        let defs = vec![def(0xf004, "snow", "mm"), def(0xf005, "Snow", "cm")];
+        assert!(r.register_all(defs).is_err());
+        assert_eq!(r, KindRegistry::new());
+
+        assert_eq!(r.register_all(vec![def(0xf004, "snow", "mm")]).unwrap(), 1);
+        assert_eq!(r.find_name("SNOW"), Some(&def(0xf004, "snow", "mm")));
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn parse_kind_files() {
+        let expected = vec![def(4096, "wind_speed", "m/s")];
+
+        let toml = "[[kinds]]\nid = 4096\nname = \"wind_speed\"\nunit = \"m/s\"\n";
+        assert_eq!(parse_file(toml, false).unwrap(), expected);
+
+        let json = r#"{"kinds": [{"id": 4096, "name": "wind_speed", "unit": "m/s"}]}"#;
+        assert_eq!(parse_file(json, true).unwrap(), expected);
+
+        assert!(is_json(std::path::Path::new("kinds.JSON")));
+        assert!(!is_json(std::path::Path::new("kinds.toml")));
+    }
+}
@@ -23,6 +23,46 @@ pub enum EpValue {
     Text(String<64>),
     /// Raw data value
     Bytes(Vec<u8, 64>),
+    /// 64-bit monotonic counter value (wrapping)
+    Counter64(u64),
+    /// Duration in milliseconds
+    DurationMs(u64),
+    /// Percentage value
+    Percent(f32),
+    /// Event with a monotonic (wrapping) event counter and the time of the
+    /// most recent event in milliseconds since the Unix epoch
+    Event { count: u64, time_ms: u64 },
+}
+
+impl EpValue {
+    /// Fetch a numeric value as an f32, returning `None` for non-numeric values
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            EpValue::Int32(v) => Some(*v as f32),
+            EpValue::Float32(v) | EpValue::Percent(v) => Some(*v),
+            EpValue::Counter64(v) | EpValue::DurationMs(v) => Some(*v as f32),
+            EpValue::Event { count, .. } => Some(*count as f32),
+            _ => None,
+        }
+    }
+
+    /// Compute the change from a previous value of the same type.
+    ///
+    /// Counters are treated as wrapping so a rollover yields the correct delta,
+    /// mismatched or non-numeric values return `None`.
+    pub fn delta(&self, prev: &EpValue) -> Option<EpValue> {
+        use EpValue::*;
+
+        match (self, prev) {
+            (Int32(a), Int32(b)) => Some(Int32(a.wrapping_sub(*b))),
+            (Float32(a), Float32(b)) => Some(Float32(a - b)),
+            (Counter64(a), Counter64(b)) => Some(Counter64(a.wrapping_sub(*b))),
+            (DurationMs(a), DurationMs(b)) => Some(DurationMs(a.saturating_sub(*b))),
+            (Percent(a), Percent(b)) => Some(Percent(a - b)),
+            (Event { count: a, .. }, Event { count: b, .. }) => Some(Counter64(a.wrapping_sub(*b))),
+            _ => None,
+        }
+    }
 }
 
 impl From<bool> for EpValue {
@@ -57,7 +97,7 @@ impl TryFrom<&[u8]> for EpValue {
     }
 }
 
-impl <const N: usize> TryFrom<&[u8; N]> for EpValue {
+impl<const N: usize> TryFrom<&[u8; N]> for EpValue {
     type Error = ();
 
     fn try_from(v: &[u8; N]) -> Result<Self, Self::Error> {
@@ -76,6 +116,22 @@ impl Display for EpValue {
             },
             EpValue::Bool(v) => Display::fmt(v, f),
             EpValue::Bytes(v) => write!(f, "{v:02x?}"),
+            EpValue::Counter64(v) => Display::fmt(v, f),
+            EpValue::DurationMs(v) => match f.width() {
+                Some(w) => write!(
+                    f,
+                    "{:w$}.{:03}s",
+                    v / 1000,
+                    v % 1000,
+                    w = w.saturating_sub(5)
+                ),
+                None => write!(f, "{}.{:03}s", v / 1000, v % 1000),
+            },
+            EpValue::Percent(v) => match f.width() {
+                Some(w) => write!(f, "{:w$.02}%", v, w = w.saturating_sub(1)),
+                None => write!(f, "{v:.02}%"),
+            },
+            EpValue::Event { count, time_ms } => write!(f, "{count}@{time_ms}ms"),
         }
     }
 }
@@ -85,12 +141,25 @@ impl FromStr for EpValue {
 
     fn from_str(src: &str) -> Result<EpValue, Self::Err> {
         // first attempt to match bools
-        if src.to_lowercase() == "true" {
+        if src.eq_ignore_ascii_case("true") {
             return Ok(EpValue::Bool(true));
-        } else if src.to_lowercase() == "false" {
+        } else if src.eq_ignore_ascii_case("false") {
             return Ok(EpValue::Bool(false));
         }
 
+        // Then percentages, events and durations
+        if let Some(v) = src.strip_suffix('%').and_then(|v| f32::from_str(v).ok()) {
+            return Ok(EpValue::Percent(v));
+        }
+        if let Some((c, t)) = src.strip_suffix("ms").and_then(|v| v.split_once('@')) {
+            if let (Ok(count), Ok(time_ms)) = (u64::from_str(c), u64::from_str(t)) {
+                return Ok(EpValue::Event { count, time_ms });
+            }
+        }
+        if let Some(v) = src.strip_suffix("ms").and_then(|v| u64::from_str(v).ok()) {
+            return Ok(EpValue::DurationMs(v));
+        }
+
         // Then floats
         if let Ok(v) = f32::from_str(src) {
             return Ok(EpValue::Float32(v));
@@ -104,6 +173,59 @@ impl FromStr for EpValue {
 }
 
 /// Helper to parse endpoint data from string values
-pub(crate) fn parse_endpoint_value(src: &str) -> Result<EpValue, IotError> {
+pub fn parse_endpoint_value(src: &str) -> Result<EpValue, IotError> {
     EpValue::from_str(src)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn value_delta() {
+        let a = EpValue::Counter64(5);
+        let b = EpValue::Counter64(u64::MAX - 4);
+        assert_eq!(a.delta(&b), Some(EpValue::Counter64(10)));
+
+        let a = EpValue::DurationMs(1500);
+        let b = EpValue::DurationMs(500);
+        assert_eq!(a.delta(&b), Some(EpValue::DurationMs(1000)));
+
+        let a = EpValue::Event {
+            count: 7,
+            time_ms: 2000,
+        };
+        let b = EpValue::Event {
+            count: 4,
+            time_ms: 1000,
+        };
+        assert_eq!(a.delta(&b), Some(EpValue::Counter64(3)));
+
+        assert_eq!(EpValue::Int32(1).delta(&EpValue::Float32(1.0)), None);
+        assert_eq!(EpValue::Bool(true).delta(&EpValue::Bool(false)), None);
+    }
+
+    #[test]
+    fn value_parse_display() {
+        let tests = &[
+            ("45.5%", EpValue::Percent(45.5), "45.50%"),
+            ("1500ms", EpValue::DurationMs(1500), "1.500s"),
+            ("true", EpValue::Bool(true), "true"),
+            ("12.5", EpValue::Float32(12.5), "12.50"),
+            (
+                "3@1700000000123ms",
+                EpValue::Event {
+                    count: 3,
+                    time_ms: 1_700_000_000_123,
+                },
+                "3@1700000000123ms",
+            ),
+        ];
+
+        for (s, v, d) in tests {
+            let p = EpValue::from_str(s).unwrap();
+            assert_eq!(&p, v);
+            assert_eq!(&p.to_string(), d);
+        }
+    }
+}
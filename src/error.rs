@@ -1,6 +1,5 @@
 #[derive(Debug)]
 #[cfg_attr(feature = "thiserror", derive(thiserror::Error))]
-#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum IotError {
     #[cfg_attr(feature = "thiserror", error("core error: {0}"))]
     Core(dsf_core::error::Error),
@@ -27,6 +26,159 @@ pub enum IotError {
 
     #[cfg_attr(feature = "thiserror", error("Overrun in static vector"))]
     Overrun,
+
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Too many endpoints: {count} (max: {max})")
+    )]
+    TooManyEndpoints { count: usize, max: usize },
+
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Encoded object too large: {needed} bytes")
+    )]
+    EncodeOverflow { needed: usize },
+
+    #[cfg_attr(feature = "thiserror", error("No endpoints or data provided"))]
+    EmptyData,
+
+    #[cfg_attr(feature = "thiserror", error("Duplicate endpoint kind: {0}"))]
+    DuplicateEndpoint(u16),
+
+    #[cfg_attr(feature = "thiserror", error("Unsupported unit conversion"))]
+    UnsupportedConversion,
+
+    #[cfg_attr(feature = "thiserror", error("No command specified"))]
+    NoCommand,
+
+    #[cfg_attr(feature = "thiserror", error("Endpoint not found"))]
+    NoEndpoint,
+
+    #[cfg_attr(feature = "thiserror", error("Mismatched endpoint value type"))]
+    MismatchedType,
+
+    #[cfg_attr(feature = "thiserror", error("Invalid composite source"))]
+    InvalidSource,
+
+    #[cfg_attr(feature = "thiserror", error("Hardware peripheral error"))]
+    Hal,
+
+    #[cfg_attr(feature = "thiserror", error("No search criteria provided"))]
+    NoCriteria,
+
+    #[cfg_attr(feature = "thiserror", error("Invalid key file"))]
+    InvalidKeyFile,
+
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Invalid passphrase or corrupted key file")
+    )]
+    KeyDecrypt,
+
+    #[cfg_attr(feature = "thiserror", error("Value outside endpoint constraints"))]
+    OutOfRange,
+
+    #[cfg_attr(feature = "thiserror", error("Endpoint is read-only"))]
+    ReadOnly,
+
+    #[cfg_attr(feature = "thiserror", error("Invalid endpoint descriptor"))]
+    InvalidDescriptor,
+
+    #[cfg_attr(feature = "thiserror", error("Invalid location"))]
+    InvalidLocation,
+
+    #[cfg_attr(feature = "thiserror", error("Invalid manifest entry (line {line})"))]
+    InvalidManifest { line: usize },
+
+    #[cfg_attr(feature = "thiserror", error("Unrecognised device class"))]
+    UnrecognisedClass,
+
+    #[cfg_attr(feature = "thiserror", error("No matching service found"))]
+    NoService,
+
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Ambiguous service name: {count} matching services")
+    )]
+    AmbiguousService { count: usize },
+
+    #[cfg(feature = "client")]
+    #[cfg_attr(feature = "thiserror", error("toml error: {0}"))]
+    Toml(toml::de::Error),
+
+    #[cfg(feature = "client")]
+    #[cfg_attr(feature = "thiserror", error("toml encode error: {0}"))]
+    TomlEncode(toml::ser::Error),
+
+    #[cfg(feature = "client")]
+    #[cfg_attr(feature = "thiserror", error("json error: {0}"))]
+    Json(serde_json::Error),
+}
+
+impl IotError {
+    /// Compact numeric error code, for logging on constrained devices
+    pub fn code(&self) -> u8 {
+        match self {
+            Self::Core(_) => 0x01,
+            #[cfg(feature = "std")]
+            Self::Client(_) => 0x02,
+            #[cfg(feature = "std")]
+            Self::Io(_) => 0x03,
+            Self::Encdec(_) => 0x04,
+            Self::NoSecretKey => 0x10,
+            Self::NoBody => 0x11,
+            Self::UnrecognisedEndpoint => 0x12,
+            Self::Overrun => 0x13,
+            Self::TooManyEndpoints { .. } => 0x14,
+            Self::EncodeOverflow { .. } => 0x15,
+            Self::EmptyData => 0x16,
+            Self::DuplicateEndpoint(_) => 0x17,
+            Self::UnsupportedConversion => 0x18,
+            Self::NoCommand => 0x19,
+            Self::NoEndpoint => 0x1a,
+            Self::MismatchedType => 0x1b,
+            Self::InvalidSource => 0x1c,
+            Self::Hal => 0x1d,
+            Self::NoCriteria => 0x1e,
+            Self::InvalidKeyFile => 0x30,
+            Self::KeyDecrypt => 0x31,
+            Self::OutOfRange => 0x32,
+            Self::ReadOnly => 0x33,
+            Self::InvalidDescriptor => 0x34,
+            Self::InvalidLocation => 0x35,
+            Self::InvalidManifest { .. } => 0x36,
+            Self::NoService => 0x37,
+            Self::AmbiguousService { .. } => 0x38,
+            Self::UnrecognisedClass => 0x39,
+            #[cfg(feature = "client")]
+            Self::Toml(_) => 0x20,
+            #[cfg(feature = "client")]
+            Self::TomlEncode(_) => 0x21,
+            #[cfg(feature = "client")]
+            Self::Json(_) => 0x22,
+        }
+    }
+}
+
+/// Compact defmt formatting using error codes, nested errors without
+/// defmt support (std-only and encoding errors) are reported by code only
+#[cfg(feature = "defmt")]
+impl defmt::Format for IotError {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Self::Core(e) => defmt::write!(f, "E{=u8:02x} ({})", self.code(), e),
+            Self::TooManyEndpoints { count, max } => {
+                defmt::write!(f, "E{=u8:02x} ({=usize}/{=usize})", self.code(), count, max)
+            }
+            Self::EncodeOverflow { needed } => {
+                defmt::write!(f, "E{=u8:02x} ({=usize})", self.code(), needed)
+            }
+            Self::DuplicateEndpoint(k) => {
+                defmt::write!(f, "E{=u8:02x} ({=u16:04x})", self.code(), k)
+            }
+            _ => defmt::write!(f, "E{=u8:02x}", self.code()),
+        }
+    }
 }
 
 #[cfg(feature = "std")]
@@ -49,6 +201,27 @@ impl From<std::io::Error> for IotError {
     }
 }
 
+#[cfg(feature = "client")]
+impl From<toml::de::Error> for IotError {
+    fn from(e: toml::de::Error) -> Self {
+        Self::Toml(e)
+    }
+}
+
+#[cfg(feature = "client")]
+impl From<toml::ser::Error> for IotError {
+    fn from(e: toml::ser::Error) -> Self {
+        Self::TomlEncode(e)
+    }
+}
+
+#[cfg(feature = "client")]
+impl From<serde_json::Error> for IotError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
 impl From<encdec::Error> for IotError {
     fn from(e: encdec::Error) -> Self {
         Self::Encdec(e)
@@ -0,0 +1,322 @@
+//! Generic [`embedded_hal`] drivers mapping ADC channels and GPIO pins to
+//! endpoints, allowing simple boards to expose sensors and actuators
+//! without per-device driver code.
+
+use core::cell::RefCell;
+use core::marker::PhantomData;
+
+use embedded_hal::adc::{Channel, OneShot};
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+use crate::endpoint::{
+    EpData, EpDescriptor, EpFlags, EpKind, EpValue, IotData, IotInfo, MAX_ENDPOINTS,
+};
+use crate::error::IotError;
+
+/// Endpoint channel backed by a hardware peripheral
+pub trait EpChannel {
+    /// Fetch the endpoint descriptor for this channel
+    fn descriptor(&self) -> EpDescriptor;
+
+    /// Read the current endpoint value
+    fn read(&mut self) -> Result<EpValue, IotError>;
+
+    /// Write an endpoint value, returning [`IotError::NoEndpoint`] for read-only channels
+    fn write(&mut self, _value: &EpValue) -> Result<(), IotError> {
+        Err(IotError::NoEndpoint)
+    }
+}
+
+/// Linear scaling from raw readings to endpoint values, `value = raw * scale + offset`
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Scaling {
+    pub scale: f32,
+    pub offset: f32,
+}
+
+impl Default for Scaling {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            offset: 0.0,
+        }
+    }
+}
+
+impl Scaling {
+    /// Create a scaling mapping the raw range `0..=max` to `min_value..=max_value`
+    pub fn range(max: u32, min_value: f32, max_value: f32) -> Self {
+        Self {
+            scale: (max_value - min_value) / max as f32,
+            offset: min_value,
+        }
+    }
+
+    /// Apply scaling to a raw reading
+    pub fn apply(&self, raw: u32) -> f32 {
+        raw as f32 * self.scale + self.offset
+    }
+}
+
+/// Analog input endpoint, reading a (shared) ADC channel with linear scaling
+pub struct AnalogInput<'a, ADC, A, W, PIN> {
+    adc: &'a RefCell<A>,
+    pin: PIN,
+    kind: EpKind,
+    scaling: Scaling,
+    _adc: PhantomData<(ADC, W)>,
+}
+
+impl<'a, ADC, A, W, PIN> AnalogInput<'a, ADC, A, W, PIN>
+where
+    A: OneShot<ADC, W, PIN>,
+    PIN: Channel<ADC>,
+    W: Into<u32>,
+{
+    /// Create a new analog input endpoint
+    pub fn new(adc: &'a RefCell<A>, pin: PIN, kind: EpKind, scaling: Scaling) -> Self {
+        Self {
+            adc,
+            pin,
+            kind,
+            scaling,
+            _adc: PhantomData,
+        }
+    }
+}
+
+impl<'a, ADC, A, W, PIN> EpChannel for AnalogInput<'a, ADC, A, W, PIN>
+where
+    A: OneShot<ADC, W, PIN>,
+    PIN: Channel<ADC>,
+    W: Into<u32>,
+{
+    fn descriptor(&self) -> EpDescriptor {
+        EpDescriptor::new(self.kind.clone(), EpFlags::R)
+    }
+
+    fn read(&mut self) -> Result<EpValue, IotError> {
+        let mut adc = self.adc.borrow_mut();
+
+        // Block until the conversion is complete
+        let raw = loop {
+            match adc.read(&mut self.pin) {
+                Ok(v) => break v,
+                Err(nb::Error::WouldBlock) => continue,
+                Err(nb::Error::Other(_)) => return Err(IotError::Hal),
+            }
+        };
+
+        Ok(EpValue::Float32(self.scaling.apply(raw.into())))
+    }
+}
+
+/// Digital input endpoint, reading a GPIO pin as a boolean value
+pub struct DigitalInput<PIN> {
+    pin: PIN,
+    kind: EpKind,
+    inverted: bool,
+}
+
+impl<PIN: InputPin> DigitalInput<PIN> {
+    /// Create a new digital input endpoint, `inverted` for active-low inputs
+    pub fn new(pin: PIN, kind: EpKind, inverted: bool) -> Self {
+        Self {
+            pin,
+            kind,
+            inverted,
+        }
+    }
+}
+
+impl<PIN: InputPin> EpChannel for DigitalInput<PIN> {
+    fn descriptor(&self) -> EpDescriptor {
+        EpDescriptor::new(self.kind.clone(), EpFlags::R)
+    }
+
+    fn read(&mut self) -> Result<EpValue, IotError> {
+        let v = self.pin.is_high().map_err(|_| IotError::Hal)?;
+        Ok(EpValue::Bool(v != self.inverted))
+    }
+}
+
+/// Digital output endpoint, driving a GPIO pin from boolean values
+pub struct DigitalOutput<PIN> {
+    pin: PIN,
+    kind: EpKind,
+    inverted: bool,
+    state: bool,
+}
+
+impl<PIN: OutputPin> DigitalOutput<PIN> {
+    /// Create a new digital output endpoint, `inverted` for active-low outputs.
+    /// The output is driven to the inactive state on creation.
+    pub fn new(pin: PIN, kind: EpKind, inverted: bool) -> Result<Self, IotError> {
+        let mut s = Self {
+            pin,
+            kind,
+            inverted,
+            state: false,
+        };
+        s.set(false)?;
+        Ok(s)
+    }
+
+    fn set(&mut self, state: bool) -> Result<(), IotError> {
+        let r = match state != self.inverted {
+            true => self.pin.set_high(),
+            false => self.pin.set_low(),
+        };
+        r.map_err(|_| IotError::Hal)?;
+
+        self.state = state;
+        Ok(())
+    }
+}
+
+impl<PIN: OutputPin> EpChannel for DigitalOutput<PIN> {
+    fn descriptor(&self) -> EpDescriptor {
+        EpDescriptor::new(self.kind.clone(), EpFlags::RW)
+    }
+
+    fn read(&mut self) -> Result<EpValue, IotError> {
+        Ok(EpValue::Bool(self.state))
+    }
+
+    fn write(&mut self, value: &EpValue) -> Result<(), IotError> {
+        match value {
+            EpValue::Bool(v) => self.set(*v),
+            _ => Err(IotError::MismatchedType),
+        }
+    }
+}
+
+/// Generic driver over a set of endpoint channels, in endpoint order
+pub struct HalDriver<'a, const N: usize = MAX_ENDPOINTS> {
+    channels: &'a mut [&'a mut dyn EpChannel],
+}
+
+impl<'a, const N: usize> HalDriver<'a, N> {
+    /// Create a new driver over the provided channels
+    pub fn new(channels: &'a mut [&'a mut dyn EpChannel]) -> Result<Self, IotError> {
+        if channels.len() > N {
+            return Err(IotError::TooManyEndpoints {
+                count: channels.len(),
+                max: N,
+            });
+        }
+
+        Ok(Self { channels })
+    }
+
+    /// Build endpoint descriptors for the service
+    pub fn info(&self) -> IotInfo<N> {
+        let mut info = IotInfo::default();
+        for c in self.channels.iter() {
+            // Channel count is checked on creation
+            let _ = info.descriptors.push(c.descriptor());
+        }
+        info
+    }
+
+    /// Read all channels to an endpoint data object
+    pub fn read(&mut self) -> Result<IotData<N>, IotError> {
+        let mut data = IotData::new(&[])?;
+        for c in self.channels.iter_mut() {
+            let v = c.read()?;
+            let _ = data.data.push(EpData::new(v));
+        }
+        Ok(data)
+    }
+
+    /// Write a value to the channel at the provided endpoint index,
+    /// validated against the channel descriptor
+    pub fn write(&mut self, index: usize, value: &EpValue) -> Result<(), IotError> {
+        let c = self.channels.get_mut(index).ok_or(IotError::NoEndpoint)?;
+
+        c.descriptor().validate(value)?;
+        c.write(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::convert::Infallible;
+
+    struct MockAdc;
+    struct MockAdcPin(u16);
+
+    impl Channel<MockAdc> for MockAdcPin {
+        type ID = u8;
+        fn channel() -> u8 {
+            0
+        }
+    }
+
+    impl OneShot<MockAdc, u16, MockAdcPin> for MockAdc {
+        type Error = ();
+        fn read(&mut self, pin: &mut MockAdcPin) -> nb::Result<u16, ()> {
+            Ok(pin.0)
+        }
+    }
+
+    struct MockPin(bool);
+
+    impl InputPin for MockPin {
+        type Error = Infallible;
+        fn is_high(&self) -> Result<bool, Infallible> {
+            Ok(self.0)
+        }
+        fn is_low(&self) -> Result<bool, Infallible> {
+            Ok(!self.0)
+        }
+    }
+
+    impl OutputPin for MockPin {
+        type Error = Infallible;
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            self.0 = true;
+            Ok(())
+        }
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            self.0 = false;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn hal_driver_channels() {
+        let adc = RefCell::new(MockAdc);
+
+        let mut a = AnalogInput::new(
+            &adc,
+            MockAdcPin(2048),
+            EpKind::Brightness,
+            Scaling::range(4096, 0.0, 100.0),
+        );
+        let mut i = DigitalInput::new(MockPin(false), EpKind::State, true);
+        let mut o = DigitalOutput::new(MockPin(true), EpKind::State, false).unwrap();
+
+        let mut channels: [&mut dyn EpChannel; 3] = [&mut a, &mut i, &mut o];
+        let mut d = HalDriver::<4>::new(&mut channels).unwrap();
+
+        let info = d.info();
+        assert_eq!(info.descriptors[0].kind, EpKind::Brightness);
+        assert_eq!(info.descriptors[2].flags, EpFlags::RW);
+
+        let data = d.read().unwrap();
+        assert_eq!(data.data[0].value, EpValue::Float32(50.0));
+        assert_eq!(data.data[1].value, EpValue::Bool(true));
+        assert_eq!(data.data[2].value, EpValue::Bool(false));
+
+        d.write(2, &EpValue::Bool(true)).unwrap();
+        assert_eq!(d.read().unwrap().data[2].value, EpValue::Bool(true));
+
+        assert!(d.write(0, &EpValue::Bool(true)).is_err());
+        assert!(d.write(2, &EpValue::Int32(1)).is_err());
+        assert!(d.write(4, &EpValue::Bool(true)).is_err());
+    }
+}
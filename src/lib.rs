@@ -13,24 +13,37 @@ use dsf_engine::engine::Engine;
 pub mod endpoint;
 pub mod error;
 pub mod prelude;
+use endpoint::MAX_ENDPOINTS;
 use prelude::EpDescriptor;
 
 #[cfg(feature = "client")]
 pub mod client;
 
-/// IoT application marker object
-pub struct IoT;
+#[cfg(feature = "client")]
+pub mod senml;
+
+#[cfg(feature = "hal")]
+pub mod hal;
+
+#[cfg(feature = "std")]
+pub mod sim;
+
+/// IoT application marker object, supporting up to `N` endpoints per service
+pub struct IotApp<const N: usize = MAX_ENDPOINTS>;
+
+/// IoT application with the default endpoint limit
+pub type IoT = IotApp<MAX_ENDPOINTS>;
 
 /// IoT application specification
-impl Application for IoT {
+impl<const N: usize> Application for IotApp<N> {
     /// IoT is the first DSF application
     const APPLICATION_ID: u16 = 1;
 
     /// IotInfo object contains endpoint descriptors
-    type Info = endpoint::IotInfo;
+    type Info = endpoint::IotInfo<N>;
 
     /// IotData object contains endpoint data
-    type Data = endpoint::IotData;
+    type Data = endpoint::IotData<N>;
 
     /// Helper to match our service against a discovery request
     fn matches(body: &Self::Info, req: &[u8]) -> bool {
@@ -51,8 +64,9 @@ impl Application for IoT {
     }
 }
 
-/// IoT engine type alias
-pub type IotEngine<Comms, Stor, const N: usize = 512> = Engine<IoT, Comms, Stor, N>;
+/// IoT engine type alias, with an `N` byte object buffer and up to `E` endpoints
+pub type IotEngine<Comms, Stor, const N: usize = 512, const E: usize = MAX_ENDPOINTS> =
+    Engine<IotApp<E>, Comms, Stor, N>;
 
 #[cfg(feature = "defmt")]
 mod log {
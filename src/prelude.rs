@@ -1,10 +1,16 @@
 //! Prelude to simplify use of `dsf_iot` crate
 
-pub use crate::endpoint::{EpData, EpDescriptor, EpFlags, EpKind, IotData, IotInfo};
+pub use crate::endpoint::{
+    EpCalibration, EpConstraints, EpData, EpDescriptor, EpFlags, EpKind, EpQuality, EpReadings,
+    EpRole, EpValue, IotData, IotInfo, MAX_ENDPOINTS,
+};
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub use crate::endpoint::UnitSystem;
 
 #[cfg(feature = "client")]
-pub use crate::client::{options::*, Config, IotClient, ServiceIdentifier};
+pub use crate::client::{options::*, Config, IotClient, IotServiceHandle, ServiceIdentifier};
 
 pub use crate::error::IotError;
 
-pub use crate::{IoT, IotEngine};
+pub use crate::{IoT, IotApp, IotEngine};
@@ -0,0 +1,360 @@
+//! SenML ([RFC 8428](https://www.rfc-editor.org/rfc/rfc8428)) export of IoT data,
+//! for ingestion by standard IoT backends.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use dsf_core::options::Options;
+use dsf_core::prelude::MaybeEncrypted;
+use dsf_rpc::{DataInfo, ServiceInfo};
+
+use crate::endpoint::{EpData, EpDescriptor, EpKind, EpValue};
+use crate::error::IotError;
+
+/// SenML record, fields are named per RFC 8428 section 4
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct SenmlRecord {
+    /// Base name, prepended to the name of this and following records
+    #[serde(rename = "bn", skip_serializing_if = "Option::is_none")]
+    pub base_name: Option<String>,
+
+    /// Base time, added to the time of this and following records
+    #[serde(rename = "bt", skip_serializing_if = "Option::is_none")]
+    pub base_time: Option<f64>,
+
+    /// Record name
+    #[serde(rename = "n")]
+    pub name: String,
+
+    /// Record unit (from the SenML units registry)
+    #[serde(rename = "u", skip_serializing_if = "Option::is_none")]
+    pub unit: Option<&'static str>,
+
+    /// Numeric value
+    #[serde(rename = "v", skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+
+    /// String value
+    #[serde(rename = "vs", skip_serializing_if = "Option::is_none")]
+    pub string_value: Option<String>,
+
+    /// Boolean value
+    #[serde(rename = "vb", skip_serializing_if = "Option::is_none")]
+    pub bool_value: Option<bool>,
+
+    /// Data value (base64url encoded in JSON)
+    #[serde(
+        rename = "vd",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_base64url"
+    )]
+    pub data_value: Option<Vec<u8>>,
+
+    /// Record time
+    #[serde(rename = "t", skip_serializing_if = "Option::is_none")]
+    pub time: Option<f64>,
+}
+
+/// Map endpoint kinds to SenML units and scales (for non-SenML base units)
+fn senml_unit(kind: &EpKind) -> (Option<&'static str>, f64) {
+    match kind {
+        EpKind::Temperature => (Some("Cel"), 1.0),
+        EpKind::Humidity => (Some("%RH"), 1.0),
+        EpKind::Pressure => (Some("Pa"), 1000.0),
+        EpKind::Co2 => (Some("ppm"), 1.0),
+        EpKind::Brightness => (Some("%"), 1.0),
+        EpKind::Rssi => (Some("dBm"), 1.0),
+        EpKind::ErrorCount => (Some("count"), 1.0),
+        EpKind::Power => (Some("W"), 1.0),
+        EpKind::Energy => (Some("J"), 3_600_000.0),
+        _ => (None, 1.0),
+    }
+}
+
+/// Build SenML records for a set of endpoint values with matching descriptors
+pub fn records(
+    base_name: &str,
+    time: Option<f64>,
+    descriptors: &[EpDescriptor],
+    data: &[EpData],
+) -> Vec<SenmlRecord> {
+    let mut records = Vec::with_capacity(data.len());
+
+    for (i, d) in data.iter().enumerate() {
+        // Resolve endpoint names and units using descriptors
+        let (name, (unit, scale), value) = match descriptors.get(i) {
+            Some(e) => (
+                e.kind.to_string(),
+                senml_unit(&e.kind),
+                e.calibration.apply(&d.value),
+            ),
+            None => (format!("{}", i), (None, 1.0), d.value.clone()),
+        };
+
+        let mut r = SenmlRecord {
+            name,
+            unit,
+            ..Default::default()
+        };
+
+        match &value {
+            EpValue::Bool(v) => r.bool_value = Some(*v),
+            EpValue::Int32(v) => r.value = Some(*v as f64 * scale),
+            EpValue::Float32(v) => r.value = Some(*v as f64 * scale),
+            EpValue::Text(v) => r.string_value = Some(v.to_string()),
+            EpValue::Bytes(v) => r.data_value = Some(v.to_vec()),
+            EpValue::Counter64(v) => r.value = Some(*v as f64),
+            EpValue::DurationMs(v) => {
+                r.unit = Some("s");
+                r.value = Some(*v as f64 / 1000.0);
+            }
+            EpValue::Percent(v) => {
+                r.unit = Some("%");
+                r.value = Some(*v as f64);
+            }
+            EpValue::Event { count, .. } => {
+                r.unit = Some("count");
+                r.value = Some(*count as f64);
+            }
+        }
+
+        records.push(r);
+    }
+
+    // Base name and time are attached to the first record
+    if let Some(r) = records.first_mut() {
+        r.base_name = Some(base_name.to_string());
+        r.base_time = time;
+    }
+
+    records
+}
+
+/// Build SenML records for service data returned by [`crate::client::IotClient::query`]
+pub fn service_records(
+    service: &ServiceInfo,
+    desc: &DataInfo<Vec<EpDescriptor>>,
+    data: &[DataInfo<Vec<EpData>>],
+) -> Vec<SenmlRecord> {
+    let base_name = format!("urn:dsf:{}:", service.id);
+
+    let descriptors = match &desc.body {
+        MaybeEncrypted::Cleartext(eps) => &eps[..],
+        _ => &[],
+    };
+
+    let mut r = vec![];
+    for d in data {
+        if let MaybeEncrypted::Cleartext(values) = &d.body {
+            r.extend(records(&base_name, object_time(d), descriptors, values));
+        }
+    }
+
+    r
+}
+
+/// Fetch the issue time of a data object in seconds since the unix epoch,
+/// using the object `Issued` option
+fn object_time(data: &DataInfo<Vec<EpData>>) -> Option<f64> {
+    data.public_options.iter().find_map(|o| match o {
+        Options::Issued(t) => SystemTime::from(*t)
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|d| d.as_secs_f64()),
+        _ => None,
+    })
+}
+
+/// Encode SenML records to JSON
+pub fn to_json(records: &[SenmlRecord]) -> Result<String, IotError> {
+    let s = serde_json::to_string(records)?;
+    Ok(s)
+}
+
+/// Encode SenML records to CBOR (using integer labels per RFC 8428 section 6)
+pub fn to_cbor(records: &[SenmlRecord]) -> Vec<u8> {
+    let mut buff = vec![];
+
+    cbor_head(&mut buff, 4, records.len() as u64);
+
+    for r in records {
+        let mut fields = 0;
+        let mut body = vec![];
+
+        if let Some(v) = &r.base_name {
+            cbor_int(&mut body, -2);
+            cbor_text(&mut body, v);
+            fields += 1;
+        }
+        if let Some(v) = r.base_time {
+            cbor_int(&mut body, -3);
+            cbor_float(&mut body, v);
+            fields += 1;
+        }
+
+        cbor_int(&mut body, 0);
+        cbor_text(&mut body, &r.name);
+        fields += 1;
+
+        if let Some(v) = r.unit {
+            cbor_int(&mut body, 1);
+            cbor_text(&mut body, v);
+            fields += 1;
+        }
+        if let Some(v) = r.value {
+            cbor_int(&mut body, 2);
+            cbor_float(&mut body, v);
+            fields += 1;
+        }
+        if let Some(v) = &r.string_value {
+            cbor_int(&mut body, 3);
+            cbor_text(&mut body, v);
+            fields += 1;
+        }
+        if let Some(v) = r.bool_value {
+            cbor_int(&mut body, 4);
+            body.push(if v { 0xf5 } else { 0xf4 });
+            fields += 1;
+        }
+        if let Some(v) = r.time {
+            cbor_int(&mut body, 6);
+            cbor_float(&mut body, v);
+            fields += 1;
+        }
+        if let Some(v) = &r.data_value {
+            // Data values are carried as byte strings in CBOR
+            cbor_int(&mut body, 8);
+            cbor_head(&mut body, 2, v.len() as u64);
+            body.extend_from_slice(v);
+            fields += 1;
+        }
+
+        cbor_head(&mut buff, 5, fields);
+        buff.extend_from_slice(&body);
+    }
+
+    buff
+}
+
+/// Write a CBOR major type and argument
+fn cbor_head(buff: &mut Vec<u8>, major: u8, v: u64) {
+    let m = major << 5;
+    match v {
+        0..=23 => buff.push(m | v as u8),
+        24..=0xff => buff.extend_from_slice(&[m | 24, v as u8]),
+        0x100..=0xffff => {
+            buff.push(m | 25);
+            buff.extend_from_slice(&(v as u16).to_be_bytes());
+        }
+        0x10000..=0xffff_ffff => {
+            buff.push(m | 26);
+            buff.extend_from_slice(&(v as u32).to_be_bytes());
+        }
+        _ => {
+            buff.push(m | 27);
+            buff.extend_from_slice(&v.to_be_bytes());
+        }
+    }
+}
+
+fn cbor_int(buff: &mut Vec<u8>, v: i64) {
+    match v < 0 {
+        true => cbor_head(buff, 1, (-1 - v) as u64),
+        false => cbor_head(buff, 0, v as u64),
+    }
+}
+
+fn cbor_text(buff: &mut Vec<u8>, v: &str) {
+    cbor_head(buff, 3, v.len() as u64);
+    buff.extend_from_slice(v.as_bytes());
+}
+
+fn cbor_float(buff: &mut Vec<u8>, v: f64) {
+    buff.push(0xfb);
+    buff.extend_from_slice(&v.to_be_bytes());
+}
+
+const BASE64URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Unpadded base64url encoding, as required for SenML data values
+fn base64url(data: &[u8]) -> String {
+    let mut s = String::with_capacity((data.len() * 4).div_ceil(3));
+
+    for c in data.chunks(3) {
+        let n = c
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+
+        for i in 0..c.len() + 1 {
+            s.push(BASE64URL[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+
+    s
+}
+
+fn serialize_base64url<S: serde::Serializer>(v: &Option<Vec<u8>>, s: S) -> Result<S::Ok, S::Error> {
+    match v {
+        Some(v) => s.serialize_str(&base64url(v)),
+        None => s.serialize_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoint::EpFlags;
+
+    #[test]
+    fn base64url_encode() {
+        assert_eq!(base64url(b""), "");
+        assert_eq!(base64url(b"f"), "Zg");
+        assert_eq!(base64url(b"fo"), "Zm8");
+        assert_eq!(base64url(b"foo"), "Zm9v");
+        assert_eq!(base64url(b"foob"), "Zm9vYg");
+        assert_eq!(base64url(&[0xfb, 0xff]), "-_8");
+    }
+
+    #[test]
+    fn senml_records_json() {
+        let desc = [
+            EpDescriptor::new(EpKind::Temperature, EpFlags::R),
+            EpDescriptor::new(EpKind::Pressure, EpFlags::R),
+            EpDescriptor::new(EpKind::State, EpFlags::RW),
+        ];
+        let data = [
+            EpData::new(21.5.into()),
+            EpData::new(101.0.into()),
+            EpData::new(true.into()),
+        ];
+
+        let r = records("urn:dsf:test:", None, &desc, &data);
+        let j = to_json(&r).unwrap();
+
+        assert_eq!(
+            j,
+            r#"[{"bn":"urn:dsf:test:","n":"temperature","u":"Cel","v":21.5},{"n":"pressure","u":"Pa","v":101000.0},{"n":"state","vb":true}]"#
+        );
+
+        // Object time is attached as base time on the first record
+        let r = records("urn:dsf:test:", Some(1700000000.0), &desc, &data);
+        assert_eq!(r[0].base_time, Some(1700000000.0));
+        assert_eq!(r[1].base_time, None);
+    }
+
+    #[test]
+    fn senml_records_cbor() {
+        let r = [SenmlRecord {
+            name: "state".to_string(),
+            bool_value: Some(true),
+            ..Default::default()
+        }];
+
+        assert_eq!(
+            to_cbor(&r),
+            &[0x81, 0xa2, 0x00, 0x65, b's', b't', b'a', b't', b'e', 0x04, 0xf5]
+        );
+    }
+}
@@ -1,14 +1,14 @@
-use core::marker::PhantomData;
-use core::fmt::{Display, Debug};
 use core::convert::TryFrom;
+use core::fmt::{Debug, Display};
+use core::marker::PhantomData;
 
 #[cfg(feature = "alloc")]
-use alloc::{vec::Vec, string::String};
+use alloc::{string::String, vec::Vec};
 
-use encdec::{Encode, Decode, DecodeOwned};
+use encdec::{Decode, DecodeOwned, Encode};
 
-use dsf_core::prelude::Options;
 use dsf_core::base::{Body, DataBody, PageBody};
+use dsf_core::prelude::Options;
 use dsf_core::types::*;
 use dsf_core::wire::Container;
 
@@ -36,22 +36,21 @@ pub struct IotService<EPS = Vec<ep::Descriptor>, META = Vec<Options>> {
     pub meta: META,
 }
 
-pub struct Service2<PRI=Vec<u8>, OPT=Vec<()>, DAT=Vec<u8>> {
+pub struct Service2<PRI = Vec<u8>, OPT = Vec<()>, DAT = Vec<u8>> {
     _body: PRI,
     _public_options: OPT,
     _private_options: (),
-    data: PhantomData<DAT>
+    data: PhantomData<DAT>,
 }
 
-impl <PRI, DAT> Service2<PRI, DAT> 
+impl<PRI, DAT> Service2<PRI, DAT>
 where
     PRI: Encode + DecodeOwned,
     DAT: Encode + DecodeOwned,
 {
-
 }
 
-impl <EPS, META> IotService<EPS, META>
+impl<EPS, META> IotService<EPS, META>
 where
     EPS: AsRef<ep::Descriptor>,
     META: AsRef<(String, String)>,
@@ -59,14 +58,19 @@ where
     /// Create a new IoT service instance
     pub fn new(id: Id, endpoints: EPS, meta: META) -> Self {
         Self {
-            id, secret_key: None, endpoints, meta
+            id,
+            secret_key: None,
+            endpoints,
+            meta,
         }
     }
 }
 
 impl IotService {
-    pub fn decode_page(mut p: Container, secret_key: Option<&SecretKey>) -> Result<IotService, IotError> {
-
+    pub fn decode_page(
+        mut p: Container,
+        secret_key: Option<&SecretKey>,
+    ) -> Result<IotService, IotError> {
         if let Some(sk) = secret_key {
             p.decrypt(sk)?;
         }
@@ -80,7 +84,7 @@ impl IotService {
         // TODO: pass through metadata
         let s = IotService {
             id: p.id(),
-            secret_key: secret_key.map(|sk| sk.clone() ),
+            secret_key: secret_key.map(|sk| sk.clone()),
             endpoints,
             meta: vec![],
         };
@@ -88,10 +92,7 @@ impl IotService {
         Ok(s)
     }
 
-    pub fn encode_body(
-        endpoints: &[ep::Descriptor],
-        buff: &mut [u8],
-    ) -> Result<usize, IotError> {
+    pub fn encode_body(endpoints: &[ep::Descriptor], buff: &mut [u8]) -> Result<usize, IotError> {
         let mut index = 0;
 
         // Encode each endpoint entry
@@ -118,8 +119,5 @@ impl IotService {
     }
 }
 
-
 #[cfg(test)]
-mod test {
-
-}
+mod test {}
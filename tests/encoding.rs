@@ -0,0 +1,131 @@
+//! Property tests for endpoint wire encodings
+
+use std::convert::TryFrom;
+
+use encdec::{Decode, Encode};
+use proptest::prelude::*;
+
+use dsf_iot::prelude::*;
+
+fn ep_kind() -> impl Strategy<Value = EpKind> {
+    any::<u16>().prop_map(EpKind::from)
+}
+
+fn ep_flags() -> impl Strategy<Value = EpFlags> {
+    any::<u16>().prop_map(EpFlags::from_bits_truncate)
+}
+
+fn ep_descriptor() -> impl Strategy<Value = EpDescriptor> {
+    (ep_kind(), ep_flags()).prop_map(|(k, f)| EpDescriptor::new(k, f))
+}
+
+fn ep_value() -> impl Strategy<Value = EpValue> {
+    prop_oneof![
+        any::<bool>().prop_map(EpValue::Bool),
+        any::<i32>().prop_map(EpValue::Int32),
+        any::<f32>()
+            .prop_filter("NaN", |v| !v.is_nan())
+            .prop_map(EpValue::Float32),
+        "[a-zA-Z0-9 ]{0,64}".prop_map(|s| EpValue::from(s.as_str())),
+        prop::collection::vec(any::<u8>(), 0..64)
+            .prop_map(|v| EpValue::try_from(v.as_slice()).unwrap()),
+        any::<u64>().prop_map(EpValue::Counter64),
+        any::<u64>().prop_map(EpValue::DurationMs),
+        any::<f32>()
+            .prop_filter("NaN", |v| !v.is_nan())
+            .prop_map(EpValue::Percent),
+    ]
+}
+
+fn ep_data() -> impl Strategy<Value = EpData> {
+    ep_value().prop_map(EpData::new)
+}
+
+proptest! {
+    #[test]
+    fn descriptor_round_trip(d in ep_descriptor()) {
+        let mut buff = [0u8; 128];
+        let n = d.encode(&mut buff).unwrap();
+        prop_assert_eq!(n, d.encode_len().unwrap());
+
+        let (d1, n1) = EpDescriptor::decode(&buff[..n]).unwrap();
+        prop_assert_eq!(d, d1);
+        prop_assert_eq!(n, n1);
+    }
+
+    #[test]
+    fn data_round_trip(d in ep_data()) {
+        let mut buff = [0u8; 128];
+        let n = d.encode(&mut buff).unwrap();
+        prop_assert_eq!(n, d.encode_len().unwrap());
+
+        let (d1, n1) = EpData::decode(&buff[..n]).unwrap();
+        prop_assert_eq!(d, d1);
+        prop_assert_eq!(n, n1);
+    }
+
+    #[test]
+    fn info_round_trip(d in prop::collection::vec(ep_descriptor(), 0..8)) {
+        let info = IotInfo::<8>::new(&d).unwrap();
+
+        let mut buff = [0u8; 1024];
+        let n = info.encode(&mut buff).unwrap();
+
+        let (info1, n1) = IotInfo::<8>::decode(&buff[..n]).unwrap();
+        prop_assert_eq!(&info.descriptors, &info1.descriptors);
+        prop_assert_eq!(n, n1);
+    }
+
+    #[test]
+    fn iot_data_round_trip(d in prop::collection::vec(ep_data(), 0..8)) {
+        let data = IotData::<8>::new(&d).unwrap();
+
+        let mut buff = [0u8; 1024];
+        let n = data.encode(&mut buff).unwrap();
+
+        let (data1, n1) = IotData::<8>::decode(&buff[..n]).unwrap();
+        prop_assert_eq!(&data.data, &data1.data);
+        prop_assert_eq!(n, n1);
+    }
+
+    #[test]
+    fn truncated_decode(d in prop::collection::vec(ep_data(), 1..8), cut in any::<prop::sample::Index>()) {
+        let data = IotData::<8>::new(&d).unwrap();
+
+        let mut buff = [0u8; 1024];
+        let n = data.encode(&mut buff).unwrap();
+
+        // Truncated objects must decode a subset of values or error, rather than panic
+        if let Ok((data1, _n)) = IotData::<8>::decode(&buff[..cut.index(n)]) {
+            prop_assert!(data1.data.len() < data.data.len());
+        }
+    }
+
+    #[test]
+    fn arbitrary_decode(d in prop::collection::vec(any::<u8>(), 0..256)) {
+        let _ = EpDescriptor::decode(&d);
+        let _ = EpData::decode(&d);
+        let _ = IotInfo::<8>::decode(&d);
+        let _ = IotData::<8>::decode(&d);
+    }
+}
+
+#[test]
+fn endpoint_limits() {
+    let d = vec![EpDescriptor::new(EpKind::Temperature, EpFlags::R); 12];
+
+    // Default limits reject larger services with an explicit error
+    assert!(matches!(
+        IotInfo::<MAX_ENDPOINTS>::new(&d),
+        Err(IotError::TooManyEndpoints { count: 12, max: 8 })
+    ));
+
+    // Larger limits (e.g. for gateways) encode and decode
+    let info = IotInfo::<16>::new(&d).unwrap();
+
+    let mut buff = [0u8; 1024];
+    let n = info.encode(&mut buff).unwrap();
+
+    let (info1, _n) = IotInfo::<16>::decode(&buff[..n]).unwrap();
+    assert_eq!(&info.descriptors, &info1.descriptors);
+}